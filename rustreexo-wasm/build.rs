@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::Path;
+
+// Embeds the resolved rustreexo version so `rustreexo_version()` can report it.
+// Cargo.lock sits next to the manifest, or at the workspace root when this crate
+// is built as part of a bigger workspace.
+fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let version = Path::new(&manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lock| lock.exists())
+        .and_then(|lock| {
+            println!("cargo:rerun-if-changed={}", lock.display());
+            fs::read_to_string(lock).ok()
+        })
+        .and_then(|lock| locked_version(&lock, "rustreexo"))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=RUSTREEXO_VERSION={}", version);
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines().skip_while(|line| line.trim() != name_line);
+    lines.next()?;
+    lines
+        .next()
+        .and_then(|line| line.trim().strip_prefix("version = \""))
+        .and_then(|rest| rest.strip_suffix('"'))
+        .map(str::to_string)
+}
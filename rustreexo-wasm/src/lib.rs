@@ -1,10 +1,50 @@
 use wasm_bindgen::prelude::*;
 
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
 // When the `wee_alloc` feature is enabled, use `wee_alloc` as the global
-// allocator.
+// allocator. Either way allocations are counted for `wasm_memory_stats`.
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
-static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
+static ALLOC: CountingAlloc<wee_alloc::WeeAlloc> = CountingAlloc(wee_alloc::WeeAlloc::INIT);
+
+#[cfg(not(feature = "wee_alloc"))]
+#[global_allocator]
+static ALLOC: CountingAlloc<System> = CountingAlloc(System);
+
+static HEAP_USED: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAlloc<A>(A);
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAlloc<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() {
+            HEAP_USED.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.dealloc(ptr, layout);
+        HEAP_USED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            HEAP_USED.fetch_sub(layout.size(), Ordering::Relaxed);
+            HEAP_USED.fetch_add(new_size, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+// Bytes currently allocated through the global allocator
+pub(crate) fn heap_used() -> usize {
+    HEAP_USED.load(Ordering::Relaxed)
+}
 
 // This is like the `extern` block for the `alert` function in the previous example.
 #[wasm_bindgen]
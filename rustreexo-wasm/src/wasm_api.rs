@@ -1,23 +1,83 @@
+use bitcoin_hashes::{sha512, sha512_256, Hash as _, HashEngine};
+use js_sys::{Float64Array, Uint8Array};
 use rustreexo::accumulator::{
     node_hash::{AccumulatorHash, BitcoinNodeHash},
     pollard::{Pollard, PollardAddition},
     proof::Proof,
-    stump::Stump,
+    stump::{Stump, UpdateData},
 };
 use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::fmt;
+use std::io::{self, Cursor, Read, Write};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use wasm_bindgen::prelude::*;
 
+// Error kinds exposed to JS so callers can branch without matching on messages
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UtreexoErrorKind {
+    Other,
+    // The proof doesn't hash up to the current roots (stale or bogus proof)
+    ProofVerificationFailed,
+    // The deletion hashes don't line up with the proof targets
+    DeletionNotInProof,
+    // Applying the additions left the roots out of sync with the leaf count
+    InconsistentRoots,
+    // The proof has targets but the accumulator has no leaves yet
+    EmptyAccumulator,
+    // Proofs being merged disagree on a hash they share
+    ProofConflict,
+    // A binary blob was written by a format version this build can't read
+    UnsupportedVersion,
+    // The proof was built against an accumulator with a different leaf count
+    LeafCountMismatch,
+    // An addition repeats a leaf the accumulator already remembers
+    DuplicateLeaf,
+    // The roots after a modify aren't the ones the caller expected
+    RootsMismatch,
+    // The proof carries more hashes than any path in the forest could use
+    MalformedProof,
+    // A leaf count too big for the position math
+    Overflow,
+    // The operation ran past the time budget it was given
+    Timeout,
+    // The backend has no way to do what was asked
+    Unsupported,
+    // Stored nodes don't hash up to the nodes above them
+    CorruptState,
+    // A hash isn't 64 hex characters or 32 bytes
+    InvalidHash,
+    // A JSON argument doesn't parse or has the wrong shape
+    InvalidJson,
+}
+
 // Error type for WASM API
 #[wasm_bindgen]
 #[derive(Debug, Clone)]
 pub struct UtreexoError {
+    kind: UtreexoErrorKind,
     message: String,
 }
 
+impl UtreexoError {
+    fn new(kind: UtreexoErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl UtreexoError {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> UtreexoErrorKind {
+        self.kind
+    }
+
     #[wasm_bindgen(getter)]
     pub fn message(&self) -> String {
         self.message.clone()
@@ -42,349 +102,5971 @@ impl fmt::Display for UtreexoError {
 
 impl From<&str> for UtreexoError {
     fn from(message: &str) -> Self {
-        Self {
-            message: message.to_string(),
-        }
+        Self::new(UtreexoErrorKind::Other, message)
     }
 }
 
 impl From<String> for UtreexoError {
     fn from(message: String) -> Self {
-        Self { message }
+        Self::new(UtreexoErrorKind::Other, message)
     }
 }
 
-// Hash wrapper for WASM
-#[wasm_bindgen]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Hash {
-    inner: BitcoinNodeHash,
+fn parse_hashes(js_values: Vec<JsValue>) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    js_values
+        .into_iter()
+        .map(|js_val| {
+            let hex_str = js_val.as_string().ok_or_else(|| {
+                UtreexoError::new(UtreexoErrorKind::InvalidHash, "Hash must be a string")
+            })?;
+            BitcoinNodeHash::from_str(&hex_str).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    format!("Invalid hash: {}", e),
+                )
+            })
+        })
+        .collect()
 }
 
-#[wasm_bindgen]
-impl Hash {
-    #[wasm_bindgen(constructor)]
-    pub fn new(hex: &str) -> Result<Hash, UtreexoError> {
-        let hash = BitcoinNodeHash::from_str(hex)
-            .map_err(|e| UtreexoError::from(format!("Invalid hash: {}", e)))?;
-        Ok(Hash { inner: hash })
+// rustreexo hardcodes empty nodes as their own variant that hashes like 32 zero
+// bytes, so that is the only empty hash an instance can be configured with
+fn check_empty_hash(hex: &str) -> Result<(), UtreexoError> {
+    let hash = BitcoinNodeHash::from_str(hex).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidHash,
+            format!("Invalid hash: {}", e),
+        )
+    })?;
+    if hash.iter().any(|byte| *byte != 0) {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::Unsupported,
+            "Custom empty hashes aren't supported: rustreexo always treats empty nodes as 32 zero bytes",
+        ));
     }
+    Ok(())
+}
 
-    #[wasm_bindgen]
-    pub fn from_bytes(bytes: &[u8]) -> Result<Hash, UtreexoError> {
-        if bytes.len() != 32 {
-            return Err(UtreexoError::from("Hash must be exactly 32 bytes"));
-        }
+fn parse_additions(
+    additions_json: &str,
+) -> Result<Vec<PollardAddition<BitcoinNodeHash>>, UtreexoError> {
+    // Parse additions as JSON array of {hash: string, remember: boolean}
+    let additions: Vec<serde_json::Value> = serde_json::from_str(additions_json).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse additions JSON: {}", e),
+        )
+    })?;
 
-        let mut array = [0u8; 32];
-        array.copy_from_slice(bytes);
-        Ok(Hash {
-            inner: BitcoinNodeHash::new(array),
+    additions
+        .into_iter()
+        .map(|item| {
+            let hash_str = item["hash"].as_str().ok_or_else(|| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    "Addition must have 'hash' field as string",
+                )
+            })?;
+            let remember = item["remember"].as_bool().unwrap_or(true); // Default to remembering
+
+            let hash = BitcoinNodeHash::from_str(hash_str).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    format!("Invalid hash in addition: {}", e),
+                )
+            })?;
+
+            Ok(PollardAddition { hash, remember })
         })
-    }
+        .collect()
+}
 
-    #[wasm_bindgen]
-    pub fn to_hex(&self) -> String {
-        self.inner.to_string()
+fn parse_proof(proof_json: &str) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    serde_json::from_str(proof_json).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })
+}
+
+// Proof JSON handed over as UTF-8 bytes, deserialized straight from the slice
+fn parse_proof_bytes(proof_json: &[u8]) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    serde_json::from_slice(proof_json).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse proof JSON: {}", e),
+        )
+    })
+}
+
+// Positions take 2 * leaves of room and a 64 row forest doesn't fit in u64, so bigger
+// leaf counts (only ever seen in hostile input) are refused before any position math
+const MAX_LEAVES: u64 = 1 << 62;
+
+fn check_leaves(num_leaves: u64) -> Result<(), UtreexoError> {
+    if num_leaves > MAX_LEAVES {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::Overflow,
+            format!(
+                "An accumulator of {} leaves exceeds the supported maximum of {}",
+                num_leaves, MAX_LEAVES
+            ),
+        ));
     }
+    Ok(())
+}
 
-    #[wasm_bindgen]
-    pub fn to_bytes(&self) -> Vec<u8> {
-        self.inner.as_ref().to_vec()
+// Leaf count once `additions` leaves are appended
+fn grown_leaves(num_leaves: u64, additions: usize) -> Result<u64, UtreexoError> {
+    let leaves = num_leaves.checked_add(additions as u64).ok_or_else(|| {
+        UtreexoError::new(
+            UtreexoErrorKind::Overflow,
+            format!("Adding {} leaves to {} overflows", additions, num_leaves),
+        )
+    })?;
+    check_leaves(leaves)?;
+    Ok(leaves)
+}
+
+// rustreexo zips targets with deletion hashes, so a length mismatch would silently drop
+// deletions, and a repeated target would delete one leaf for two hashes
+fn check_deletion_targets(
+    what: &str,
+    del_hashes: &[BitcoinNodeHash],
+    proof: &Proof<BitcoinNodeHash>,
+) -> Result<(), UtreexoError> {
+    if del_hashes.len() != proof.targets.len() {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::DeletionNotInProof,
+            format!(
+                "Failed to modify {}: {} deletion hashes but the proof has {} targets",
+                what,
+                del_hashes.len(),
+                proof.targets.len()
+            ),
+        ));
     }
+    let mut seen = HashSet::new();
+    if let Some(target) = proof.targets.iter().find(|target| !seen.insert(**target)) {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::DeletionNotInProof,
+            format!(
+                "Failed to modify {}: target {} is deleted more than once",
+                what, target
+            ),
+        ));
+    }
+    Ok(())
+}
 
-    #[wasm_bindgen]
-    pub fn parent_hash(left: &Hash, right: &Hash) -> Hash {
-        Hash {
-            inner: BitcoinNodeHash::parent_hash(&left.inner, &right.inner),
-        }
+// Upstream's util::remove_bit builds masks with `(2 << bit) - 1` on i32, which overflows
+// from bit 30. Stump::modify gets there with the row of an empty root a new leaf lands
+// over, so deletions and additions over empty roots are refused in taller forests.
+const MAX_DELETION_ROWS: u8 = 29;
+
+fn check_deletion_rows(
+    roots: &[BitcoinNodeHash],
+    leaves_after: u64,
+    additions: usize,
+    deletions: usize,
+) -> Result<(), UtreexoError> {
+    let forest_rows = tree_rows(leaves_after);
+    let over_empty = additions > 0 && roots.iter().any(|root| root.is_empty());
+    if forest_rows > MAX_DELETION_ROWS && (deletions > 0 || over_empty) {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::Overflow,
+            format!(
+                "Deletions and additions over empty roots aren't supported in a forest of {} rows, rustreexo's position math only handles up to {}",
+                forest_rows, MAX_DELETION_ROWS
+            ),
+        ));
     }
+    Ok(())
 }
 
-// Stump wrapper for WASM (lightweight accumulator)
-#[wasm_bindgen]
-pub struct WasmStump {
-    inner: Stump,
+fn parse_stump_record(json_str: &str) -> Result<StumpRecord, UtreexoError> {
+    let record: StumpRecord = serde_json::from_str(json_str).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse JSON: {}", e),
+        )
+    })?;
+    check_leaves(record.stump.leaves)?;
+    Ok(record)
 }
 
-impl Default for WasmStump {
-    fn default() -> Self {
-        Self::new()
+// There is one tree per set bit of `leaves`, so any other root count is rejected
+fn checked_stump(leaves: u64, roots: Vec<BitcoinNodeHash>) -> Result<Stump, UtreexoError> {
+    check_leaves(leaves)?;
+    if roots.len() != leaves.count_ones() as usize {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::InconsistentRoots,
+            format!(
+                "An accumulator of {} leaves has {} roots, got {}",
+                leaves,
+                leaves.count_ones(),
+                roots.len()
+            ),
+        ));
     }
+    Ok(Stump { leaves, roots })
 }
 
-#[wasm_bindgen]
-impl WasmStump {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> WasmStump {
-        WasmStump {
-            inner: Stump::new(),
-        }
+// Any target is a nonexistent leaf when there are no leaves, which upstream only
+// reports as a confusing missing-sibling failure
+fn check_not_empty(num_leaves: u64, proof: &Proof<BitcoinNodeHash>) -> Result<(), UtreexoError> {
+    if num_leaves == 0 && !proof.targets.is_empty() {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::EmptyAccumulator,
+            format!(
+                "The accumulator is empty but the proof references {} leaves",
+                proof.targets.len()
+            ),
+        ));
     }
+    Ok(())
+}
 
-    #[wasm_bindgen]
-    pub fn from_json(json_str: &str) -> Result<WasmStump, UtreexoError> {
-        let stump: Stump = serde_json::from_str(json_str)
-            .map_err(|e| UtreexoError::from(format!("Failed to parse JSON: {}", e)))?;
-        Ok(WasmStump { inner: stump })
-    }
+// A proof made at another leaf count (typically before a reorg) points at positions
+// the stump doesn't have or is missing hashes for its paths, and upstream just
+// reports it as not verifying
+fn check_leaf_count(num_leaves: u64, proof: &Proof<BitcoinNodeHash>) -> Result<(), UtreexoError> {
+    check_leaves(num_leaves)?;
+    let forest_rows = tree_rows(num_leaves);
 
-    #[wasm_bindgen]
-    pub fn to_json(&self) -> Result<String, UtreexoError> {
-        serde_json::to_string(&self.inner)
-            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    // A target's path has at most one sibling per row, so anything beyond that can't
+    // be a proof at all; caught before any position math or hashing. A leaf that is
+    // its own root still gets one spare hash from prove_single, even with no rows.
+    let most = proof
+        .targets
+        .len()
+        .saturating_mul(forest_rows.max(1) as usize);
+    if proof.hashes.len() > most {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::MalformedProof,
+            format!(
+                "The proof has {} hashes but {} targets in a forest of {} rows need at most {}",
+                proof.hashes.len(),
+                proof.targets.len(),
+                forest_rows,
+                most
+            ),
+        ));
+    }
+    if let Some(target) = proof
+        .targets
+        .iter()
+        .find(|target| !in_forest(**target, num_leaves, forest_rows))
+    {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::LeafCountMismatch,
+            format!(
+                "The proof targets position {} but an accumulator of {} leaves has no such position",
+                target, num_leaves
+            ),
+        ));
     }
 
-    #[wasm_bindgen]
-    pub fn num_leaves(&self) -> u64 {
-        self.inner.leaves
+    let needed = proof_positions(&proof.targets, num_leaves).len();
+    if proof.hashes.len() < needed {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::LeafCountMismatch,
+            format!(
+                "The proof has {} hashes but proving its targets among {} leaves takes {}",
+                proof.hashes.len(),
+                num_leaves,
+                needed
+            ),
+        ));
     }
+    Ok(())
+}
 
-    #[wasm_bindgen]
-    pub fn roots(&self) -> Vec<JsValue> {
-        self.inner
-            .roots
-            .iter()
-            .map(|root| {
-                let hash = Hash { inner: *root };
-                JsValue::from(hash.to_hex())
-            })
-            .collect()
+fn parse_byte_hashes(arrays: Vec<Uint8Array>) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    arrays
+        .into_iter()
+        .map(|array| hash_from_slice(&array.to_vec()))
+        .collect()
+}
+
+fn hash_from_slice(bytes: &[u8]) -> Result<BitcoinNodeHash, UtreexoError> {
+    let array: [u8; 32] = bytes.try_into().map_err(|_| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidHash,
+            "Hash must be exactly 32 bytes",
+        )
+    })?;
+    Ok(BitcoinNodeHash::new(array))
+}
+
+// Upstream verify gives Ok(false) when the proof hashes up to other roots and Err
+// when it can't even be walked (e.g. hashes that don't line up with the targets)
+fn explain_verify(result: Result<bool, String>) -> Result<(), UtreexoError> {
+    match result {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(UtreexoError::new(
+            UtreexoErrorKind::ProofVerificationFailed,
+            "The proof doesn't hash up to the current roots; it's stale or for another accumulator",
+        )),
+        Err(e) => Err(UtreexoError::new(
+            UtreexoErrorKind::MalformedProof,
+            format!("Malformed proof: {}", e),
+        )),
     }
+}
 
-    #[wasm_bindgen]
-    pub fn verify(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<bool, UtreexoError> {
-        let proof: Proof<BitcoinNodeHash> = serde_json::from_str(proof_json)
-            .map_err(|e| UtreexoError::from(format!("Failed to parse proof JSON: {}", e)))?;
+// Hashes packed back to back, 32 bytes each, for the `_raw` methods
+fn parse_flat_hashes(bytes: &[u8]) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    if bytes.len() % 32 != 0 {
+        return Err(UtreexoError::new(
+            UtreexoErrorKind::InvalidHash,
+            format!(
+                "Hash bytes must be a multiple of 32 long, got {}",
+                bytes.len()
+            ),
+        ));
+    }
+    bytes.chunks_exact(32).map(hash_from_slice).collect()
+}
 
-        let del_hashes: Result<Vec<BitcoinNodeHash>, UtreexoError> = hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| UtreexoError::from("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| UtreexoError::from(format!("Invalid hash: {}", e)))
-            })
-            .collect();
+fn flat_hashes(hashes: &[BitcoinNodeHash]) -> Vec<u8> {
+    hashes.iter().flat_map(|hash| **hash).collect()
+}
 
-        let del_hashes = del_hashes?;
-        Ok(self.inner.verify(&proof, &del_hashes).is_ok())
+// Runs a rustreexo decoder over the whole buffer, rejecting leftover bytes
+fn decode_all<T>(
+    bytes: &[u8],
+    what: &str,
+    decode: impl FnOnce(&mut Cursor<&[u8]>) -> Result<T, String>,
+) -> Result<T, UtreexoError> {
+    let mut reader = Cursor::new(bytes);
+    let value = decode(&mut reader)
+        .map_err(|e| UtreexoError::from(format!("Failed to parse {}: {}", what, e)))?;
+    if reader.position() != bytes.len() as u64 {
+        return Err(UtreexoError::from(format!(
+            "Failed to parse {}: {} trailing bytes",
+            what,
+            bytes.len() as u64 - reader.position()
+        )));
     }
+    Ok(value)
+}
 
-    #[wasm_bindgen]
-    pub fn modify(
-        &mut self,
-        proof_json: &str,
-        add_hashes: Vec<JsValue>,
-        del_hashes: Vec<JsValue>,
-    ) -> Result<(), JsValue> {
-        let proof: Proof<BitcoinNodeHash> = serde_json::from_str(proof_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse proof JSON: {}", e)))?;
+// Proof::deserialize preallocates whatever counts the bytes claim, so a corrupt count
+// would abort on allocation; they're checked against the bytes left first
+fn read_proof(reader: &mut Cursor<&[u8]>) -> Result<Proof<BitcoinNodeHash>, String> {
+    let start = reader.position();
+    let read_count = |reader: &mut Cursor<&[u8]>, item_size: u64| {
+        let mut count = [0u8; 8];
+        reader
+            .read_exact(&mut count)
+            .map_err(|e| format!("missing count: {}", e))?;
+        let count = u64::from_le_bytes(count);
+        let left = reader.get_ref().len() as u64 - reader.position();
+        match count.checked_mul(item_size) {
+            Some(size) if size <= left => Ok(size),
+            _ => Err(format!(
+                "{} items don't fit in the {} bytes left",
+                count, left
+            )),
+        }
+    };
 
-        let add_hashes: Result<Vec<BitcoinNodeHash>, JsValue> = add_hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| JsValue::from_str("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| JsValue::from_str(&format!("Invalid hash: {}", e)))
-            })
-            .collect();
+    let targets_size = read_count(reader, 8)?;
+    reader.set_position(reader.position() + targets_size);
+    // Every tagged hash takes at least its tag byte
+    read_count(reader, 1)?;
 
-        let del_hashes: Result<Vec<BitcoinNodeHash>, JsValue> = del_hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| JsValue::from_str("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| JsValue::from_str(&format!("Invalid hash: {}", e)))
-            })
-            .collect();
+    reader.set_position(start);
+    Proof::deserialize(reader)
+}
 
-        let add_hashes = add_hashes?;
-        let del_hashes = del_hashes?;
+// Proofs on the wire use rustreexo's own encoding: LE target count and targets,
+// then LE hash count and tagged hashes
+fn parse_wire_proof(bytes: &[u8]) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    decode_all(bytes, "wire proof", read_proof)
+}
 
-        let (new_stump, _update_data) = self
-            .inner
-            .modify(&add_hashes, &del_hashes, &proof)
-            .map_err(|e| JsValue::from_str(&format!("Failed to modify stump: {}", e)))?;
+fn encode_wire_proof(proof: &Proof<BitcoinNodeHash>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    // Writing into a Vec can't fail
+    let _ = proof.serialize(&mut bytes);
+    bytes
+}
 
-        // Update the inner stump with the new state
-        self.inner = new_stump;
+// Binary formats: a format version byte, a type tag, then the rustreexo encoding of
+// the value. Anything persisted before versioning was JSON, which `migrate` upgrades.
+// Version 2 appends the stump's LE u64 sequence to its encoding; proofs are unchanged.
+const FORMAT_VERSION: u8 = 2;
+const TAG_STUMP: u8 = 1;
+const TAG_PROOF: u8 = 2;
+const TAG_WALLET: u8 = 3;
+const TAG_PROOF_CHAIN: u8 = 4;
+const TAG_POLLARD: u8 = 5;
 
-        Ok(())
-    }
+fn encode_stump(stump: &Stump, sequence: u64) -> Vec<u8> {
+    let mut bytes = vec![FORMAT_VERSION, TAG_STUMP];
+    let _ = stump.serialize(&mut bytes);
+    bytes.extend(sequence.to_le_bytes());
+    bytes
 }
 
-// Pollard wrapper for WASM (full accumulator)
-#[wasm_bindgen]
-pub struct WasmPollard {
-    inner: Pollard<BitcoinNodeHash>,
+// Length of encode_stump's output
+fn encoded_stump_len(stump: &Stump) -> usize {
+    let mut counter = ByteCounter(0);
+    let _ = stump.serialize(&mut counter);
+    2 + counter.0 + 8
 }
 
-impl Default for WasmPollard {
-    fn default() -> Self {
-        Self::new()
+// Sink that only counts what's written, for sizing encodings without building them
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
     }
-}
 
-#[wasm_bindgen]
-impl WasmPollard {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> WasmPollard {
-        WasmPollard {
-            inner: Pollard::new(),
-        }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
+}
 
-    #[wasm_bindgen]
-    pub fn from_roots(roots: Vec<JsValue>, leaves: u64) -> Result<WasmPollard, UtreexoError> {
-        let root_hashes: Result<Vec<BitcoinNodeHash>, UtreexoError> = roots
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| UtreexoError::from("Root hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| UtreexoError::from(format!("Invalid root hash: {}", e)))
-            })
-            .collect();
+// Sink keeping only `max` bytes of what's written after the first `skip`, failing
+// the write once they're filled so encoding stops early
+struct ByteWindow {
+    skip: u64,
+    max: usize,
+    bytes: Vec<u8>,
+}
 
-        let root_hashes = root_hashes?;
-        let pollard = Pollard::from_roots(root_hashes, leaves);
-        Ok(WasmPollard { inner: pollard })
+impl Write for ByteWindow {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let skipped = self.skip.min(buf.len() as u64) as usize;
+        self.skip -= skipped as u64;
+        let rest = &buf[skipped..];
+        let room = self.max - self.bytes.len();
+        if rest.len() > room {
+            self.bytes.extend_from_slice(&rest[..room]);
+            return Err(io::Error::new(io::ErrorKind::Other, "chunk is full"));
+        }
+        self.bytes.extend_from_slice(rest);
+        Ok(buf.len())
     }
 
-    #[wasm_bindgen]
-    pub fn num_leaves(&self) -> u64 {
-        self.inner.leaves()
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
+}
 
-    #[wasm_bindgen]
-    pub fn roots(&self) -> Vec<JsValue> {
-        self.inner
-            .roots()
-            .iter()
-            .map(|root| {
-                let hash = Hash { inner: *root };
-                JsValue::from(hash.to_hex())
-            })
-            .collect()
+fn encode_proof(proof: &Proof<BitcoinNodeHash>) -> Vec<u8> {
+    let mut bytes = vec![FORMAT_VERSION, TAG_PROOF];
+    bytes.extend(encode_wire_proof(proof));
+    bytes
+}
+
+fn open_envelope(bytes: &[u8], tag: u8) -> Result<&[u8], UtreexoError> {
+    match bytes {
+        [FORMAT_VERSION, found, payload @ ..] if *found == tag => Ok(payload),
+        [FORMAT_VERSION, found, ..] => Err(UtreexoError::from(format!(
+            "Expected type tag {} but found {}",
+            tag, found
+        ))),
+        [version, ..] => Err(UtreexoError::new(
+            UtreexoErrorKind::UnsupportedVersion,
+            format!(
+                "Unsupported format version {} (current is {}), run migrate first",
+                version, FORMAT_VERSION
+            ),
+        )),
+        [] => Err(UtreexoError::from("Empty buffer")),
     }
+}
 
-    #[wasm_bindgen]
-    pub fn batch_proof(&self, target_hashes: Vec<JsValue>) -> Result<String, UtreexoError> {
-        let hashes: Result<Vec<BitcoinNodeHash>, UtreexoError> = target_hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| UtreexoError::from("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| UtreexoError::from(format!("Invalid hash: {}", e)))
-            })
-            .collect();
+fn decode_stump(bytes: &[u8]) -> Result<(Stump, u64), UtreexoError> {
+    let payload = open_envelope(bytes, TAG_STUMP)?;
+    let (stump, sequence) = decode_all(payload, "stump", |reader| {
+        let stump = Stump::deserialize(&mut *reader)?;
+        let mut sequence = [0u8; 8];
+        reader
+            .read_exact(&mut sequence)
+            .map_err(|e| format!("missing sequence: {}", e))?;
+        Ok((stump, u64::from_le_bytes(sequence)))
+    })?;
+    check_leaves(stump.leaves)?;
+    Ok((stump, sequence))
+}
 
-        let hashes = hashes?;
-        let proof = self
-            .inner
-            .batch_proof(&hashes)
-            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+fn decode_proof(bytes: &[u8]) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    let payload = open_envelope(bytes, TAG_PROOF)?;
+    decode_all(payload, "proof", read_proof)
+}
 
-        serde_json::to_string(&proof)
-            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+// A wallet's slice of a pollard: the roots as a stump encoding, then an LE u64
+// count and, per leaf, its raw hash followed by its single proof in wire form
+type WalletEntry = (BitcoinNodeHash, Proof<BitcoinNodeHash>);
+
+fn encode_wallet(stump: &Stump, entries: &[WalletEntry]) -> Vec<u8> {
+    let mut bytes = vec![FORMAT_VERSION, TAG_WALLET];
+    let _ = stump.serialize(&mut bytes);
+    bytes.extend((entries.len() as u64).to_le_bytes());
+    for (hash, proof) in entries {
+        bytes.extend(**hash);
+        bytes.extend(encode_wire_proof(proof));
     }
+    bytes
+}
 
-    #[wasm_bindgen]
-    pub fn prove_single(&self, leaf_hash: &str) -> Result<String, UtreexoError> {
-        let hash = BitcoinNodeHash::from_str(leaf_hash)
-            .map_err(|e| UtreexoError::from(format!("Invalid hash: {}", e)))?;
+fn decode_wallet(bytes: &[u8]) -> Result<(Stump, Vec<WalletEntry>), UtreexoError> {
+    let payload = open_envelope(bytes, TAG_WALLET)?;
+    decode_all(payload, "wallet state", |reader| {
+        let stump = Stump::deserialize(&mut *reader)?;
+        let mut count = [0u8; 8];
+        reader
+            .read_exact(&mut count)
+            .map_err(|e| format!("missing leaf count: {}", e))?;
 
-        let proof = self
-            .inner
-            .prove_single(hash)
-            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+        let mut entries = Vec::new();
+        for _ in 0..u64::from_le_bytes(count) {
+            let mut hash = [0u8; 32];
+            reader
+                .read_exact(&mut hash)
+                .map_err(|e| format!("truncated leaf hash: {}", e))?;
+            let proof = read_proof(reader)?;
+            entries.push((BitcoinNodeHash::new(hash), proof));
+        }
+        Ok((stump, entries))
+    })
+    .and_then(|(stump, entries)| {
+        check_leaves(stump.leaves)?;
+        Ok((stump, entries))
+    })
+}
 
-        serde_json::to_string(&proof)
-            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+// A proof chain is an LE u64 block count, then per block its wire proof and three
+// LE u64 counted hash lists: deletions, additions and the roots after the block
+fn encode_proof_chain(entries: &[ChainEntry]) -> Vec<u8> {
+    let mut bytes = vec![FORMAT_VERSION, TAG_PROOF_CHAIN];
+    bytes.extend((entries.len() as u64).to_le_bytes());
+    for entry in entries {
+        bytes.extend(encode_wire_proof(&entry.proof));
+        for hashes in [&entry.deletions, &entry.additions, &entry.roots_after] {
+            bytes.extend((hashes.len() as u64).to_le_bytes());
+            for hash in hashes {
+                let _ = hash.write(&mut bytes);
+            }
+        }
     }
+    bytes
+}
 
-    #[wasm_bindgen]
-    pub fn verify(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<bool, UtreexoError> {
-        let proof: Proof<BitcoinNodeHash> = serde_json::from_str(proof_json)
-            .map_err(|e| UtreexoError::from(format!("Failed to parse proof JSON: {}", e)))?;
+fn decode_proof_chain(bytes: &[u8]) -> Result<Vec<ChainEntry>, UtreexoError> {
+    fn read_count(reader: &mut Cursor<&[u8]>) -> Result<u64, String> {
+        let mut count = [0u8; 8];
+        reader
+            .read_exact(&mut count)
+            .map_err(|e| format!("missing count: {}", e))?;
+        Ok(u64::from_le_bytes(count))
+    }
+    fn read_hashes(reader: &mut Cursor<&[u8]>) -> Result<Vec<BitcoinNodeHash>, String> {
+        // Counts aren't trusted for preallocation, a short buffer just ends the loop
+        let count = read_count(reader)?;
+        let mut hashes = Vec::new();
+        for _ in 0..count {
+            hashes.push(
+                BitcoinNodeHash::read(&mut *reader)
+                    .map_err(|e| format!("truncated hash: {}", e))?,
+            );
+        }
+        Ok(hashes)
+    }
 
-        let del_hashes: Result<Vec<BitcoinNodeHash>, UtreexoError> = hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| UtreexoError::from("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| UtreexoError::from(format!("Invalid hash: {}", e)))
-            })
-            .collect();
+    let payload = open_envelope(bytes, TAG_PROOF_CHAIN)?;
+    decode_all(payload, "proof chain", |reader| {
+        let mut entries = Vec::new();
+        for _ in 0..read_count(reader)? {
+            entries.push(ChainEntry {
+                proof: read_proof(reader)?,
+                deletions: read_hashes(reader)?,
+                additions: read_hashes(reader)?,
+                roots_after: read_hashes(reader)?,
+            });
+        }
+        Ok(entries)
+    })
+}
 
-        let del_hashes = del_hashes?;
-        Ok(self.inner.verify(&proof, &del_hashes).is_ok())
-    }
+// A pollard is rustreexo's encoding of it, then an LE u64 count and the LE u64
+// positions of its remembered leaves, which that encoding can't tell apart from inner
+// nodes once deletions moved them up
+fn write_pollard(pollard: &WasmPollard, writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&[FORMAT_VERSION, TAG_POLLARD])?;
+    pollard.inner.serialize(writer)?;
 
-    #[wasm_bindgen]
-    pub fn modify(
-        &mut self,
-        proof_json: &str,
-        additions_json: &str,
-        del_hashes: Vec<JsValue>,
-    ) -> Result<(), JsValue> {
-        let proof: Proof<BitcoinNodeHash> = serde_json::from_str(proof_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse proof JSON: {}", e)))?;
+    let remembered = pollard
+        .remembered_positions()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+    writer.write_all(&(remembered.len() as u64).to_le_bytes())?;
+    for (pos, _) in remembered {
+        writer.write_all(&pos.to_le_bytes())?;
+    }
+    Ok(())
+}
 
-        // Parse additions as JSON array of {hash: string, remember: boolean}
-        let additions: Vec<serde_json::Value> = serde_json::from_str(additions_json)
-            .map_err(|e| JsValue::from_str(&format!("Failed to parse additions JSON: {}", e)))?;
+fn decode_pollard(bytes: &[u8]) -> Result<WasmPollard, UtreexoError> {
+    let mut decoder = PollardDecoder::default();
+    decoder.push(bytes)?;
+    decoder.finish().map(WasmPollard::restored)
+}
 
-        let add_items: Result<Vec<PollardAddition<BitcoinNodeHash>>, JsValue> = additions
-            .into_iter()
-            .map(|item| {
-                let hash_str = item["hash"].as_str().ok_or_else(|| {
-                    JsValue::from_str("Addition must have 'hash' field as string")
-                })?;
-                let remember = item["remember"].as_bool().unwrap_or(true); // Default to remembering
+// Incremental decoder for write_pollard's encoding, so PollardLoader can take it a
+// chunk at a time. Stored nodes are kept by position, the bytes themselves aren't.
+#[derive(Default)]
+struct PollardDecoder {
+    received: u64,
+    envelope: Vec<u8>,
+    nodes: NodeDecoder,
+    stored: HashMap<u64, BitcoinNodeHash>,
+    // The remembered count and positions after the nodes, one LE u64 at a time
+    word: Vec<u8>,
+    count: Option<u64>,
+    positions: Vec<u64>,
+}
 
-                let hash = BitcoinNodeHash::from_str(hash_str)
-                    .map_err(|e| JsValue::from_str(&format!("Invalid hash in addition: {}", e)))?;
+impl PollardDecoder {
+    fn push(&mut self, bytes: &[u8]) -> Result<(), UtreexoError> {
+        self.received += bytes.len() as u64;
+        let take = (2 - self.envelope.len()).min(bytes.len());
+        self.envelope.extend_from_slice(&bytes[..take]);
+        let bytes = &bytes[take..];
+        match self.envelope.len() {
+            2 => open_envelope(&self.envelope, TAG_POLLARD).map(|_| ())?,
+            // Only the version byte so far
+            1 if self.envelope[0] != FORMAT_VERSION => {
+                open_envelope(&self.envelope, TAG_POLLARD).map(|_| ())?
+            }
+            _ => return Ok(()),
+        }
 
-                Ok(PollardAddition { hash, remember })
+        let stored = &mut self.stored;
+        let used = self
+            .nodes
+            .push(bytes, &mut |pos, hash| {
+                stored.insert(pos, hash);
             })
-            .collect();
+            .map_err(corrupt_pollard)?;
 
-        let del_hashes: Result<Vec<BitcoinNodeHash>, JsValue> = del_hashes
-            .into_iter()
-            .map(|js_val| {
-                let hex_str = js_val
-                    .as_string()
-                    .ok_or_else(|| JsValue::from_str("Hash must be a string"))?;
-                BitcoinNodeHash::from_str(&hex_str)
-                    .map_err(|e| JsValue::from_str(&format!("Invalid hash: {}", e)))
+        for &byte in &bytes[used..] {
+            if self.count == Some(self.positions.len() as u64) {
+                return Err(corrupt_pollard(
+                    "trailing bytes after the remembered positions".to_string(),
+                ));
+            }
+            self.word.push(byte);
+            if self.word.len() == 8 {
+                let mut word = [0u8; 8];
+                word.copy_from_slice(&self.word);
+                self.word.clear();
+                match self.count {
+                    None => self.count = Some(u64::from_le_bytes(word)),
+                    Some(_) => self.positions.push(u64::from_le_bytes(word)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Rebuilds the pollard from its roots and the remembered leaves' paths, each checked
+    // against the roots, rather than trusting rustreexo's decoding of the nodes
+    fn finish(&self) -> Result<(Pollard<BitcoinNodeHash>, HashSet<BitcoinNodeHash>), UtreexoError> {
+        if self.envelope.is_empty() {
+            return Err(UtreexoError::from("Empty buffer"));
+        }
+        let leaves = match self.nodes.leaves {
+            Some(leaves)
+                if self.nodes.is_done() && self.count == Some(self.positions.len() as u64) =>
+            {
+                leaves
+            }
+            _ => {
+                return Err(corrupt_pollard(format!(
+                    "truncated after {} bytes",
+                    self.received
+                )))
+            }
+        };
+
+        let node = |pos: u64| {
+            self.stored.get(&pos).copied().ok_or_else(|| {
+                UtreexoError::new(
+                    UtreexoErrorKind::CorruptState,
+                    format!("Failed to parse pollard: node {} is missing", pos),
+                )
             })
-            .collect();
+        };
+        let forest_rows = tree_rows(leaves);
+        let roots = (0..64u8)
+            .filter(|row| leaves >> row & 1 == 1)
+            .map(|row| node(root_position(leaves, row, forest_rows)))
+            .collect::<Result<Vec<_>, UtreexoError>>()?;
 
-        let add_items = add_items?;
-        let del_hashes = del_hashes?;
+        let mut entries = Vec::with_capacity(self.positions.len());
+        for &pos in &self.positions {
+            let hashes = proof_positions(&[pos], leaves)
+                .into_iter()
+                .map(node)
+                .collect::<Result<Vec<_>, UtreexoError>>()?;
+            entries.push((node(pos)?, Proof::new(vec![pos], hashes)));
+        }
+        let stump = checked_stump(leaves, roots.iter().rev().copied().collect())?;
+        let remembered = checked_remembered(&stump, entries)?;
 
-        self.inner
-            .modify(&add_items, &del_hashes, proof)
-            .map_err(|e| JsValue::from_str(&format!("Failed to modify pollard: {}", e)))?;
+        let hashes = remembered.iter().map(|(_, hash, _)| *hash).collect();
+        let inner = rebuild_pollard(leaves, roots, remembered)
+            .map_err(|e| UtreexoError::from(format!("Failed to rebuild pollard: {}", e)))?;
+        Ok((inner, hashes))
+    }
+}
 
-        Ok(())
+fn corrupt_pollard(message: String) -> UtreexoError {
+    UtreexoError::new(
+        UtreexoErrorKind::CorruptState,
+        format!("Failed to parse pollard: {}", message),
+    )
+}
+
+fn encode_pollard(pollard: &WasmPollard) -> Result<Vec<u8>, UtreexoError> {
+    let mut bytes = Vec::new();
+    write_pollard(pollard, &mut bytes)
+        .map_err(|e| UtreexoError::from(format!("Failed to encode pollard: {}", e)))?;
+    Ok(bytes)
+}
+
+fn migrate_inner(bytes: &[u8]) -> Result<Vec<u8>, UtreexoError> {
+    match bytes.first() {
+        // Unversioned JSON from to_json / batch_proof
+        Some(b'{') => {
+            let value: serde_json::Value = serde_json::from_slice(bytes).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidJson,
+                    format!("Failed to parse JSON: {}", e),
+                )
+            })?;
+            if value.get("leaves").is_some() {
+                let record: StumpRecord = serde_json::from_value(value).map_err(|e| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::InvalidJson,
+                        format!("Failed to parse JSON: {}", e),
+                    )
+                })?;
+                Ok(encode_stump(&record.stump, record.sequence))
+            } else {
+                let proof: Proof<BitcoinNodeHash> = serde_json::from_value(value).map_err(|e| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::InvalidJson,
+                        format!("Failed to parse proof JSON: {}", e),
+                    )
+                })?;
+                Ok(encode_proof(&proof))
+            }
+        }
+        // Version 1 stumps had no sequence yet; they start counting from 0
+        Some(1) => match bytes.get(1) {
+            Some(&TAG_STUMP) => {
+                decode_all(&bytes[2..], "stump", |reader| Stump::deserialize(reader))
+                    .map(|stump| encode_stump(&stump, 0))
+            }
+            Some(&TAG_PROOF) => {
+                decode_all(&bytes[2..], "proof", read_proof).map(|proof| encode_proof(&proof))
+            }
+            tag => Err(UtreexoError::from(format!("Unknown type tag {:?}", tag))),
+        },
+        Some(&FORMAT_VERSION) => match bytes.get(1) {
+            Some(&TAG_STUMP) => {
+                decode_stump(bytes).map(|(stump, sequence)| encode_stump(&stump, sequence))
+            }
+            Some(&TAG_PROOF) => decode_proof(bytes).map(|proof| encode_proof(&proof)),
+            Some(&TAG_WALLET) => {
+                decode_wallet(bytes).map(|(stump, entries)| encode_wallet(&stump, &entries))
+            }
+            Some(&TAG_PROOF_CHAIN) => {
+                decode_proof_chain(bytes).map(|entries| encode_proof_chain(&entries))
+            }
+            // Current pollards only need checking
+            Some(&TAG_POLLARD) => {
+                let mut decoder = PollardDecoder::default();
+                decoder.push(bytes)?;
+                decoder.finish().map(|_| bytes.to_vec())
+            }
+            tag => Err(UtreexoError::from(format!("Unknown type tag {:?}", tag))),
+        },
+        Some(version) => Err(UtreexoError::new(
+            UtreexoErrorKind::UnsupportedVersion,
+            format!(
+                "Unsupported format version {} (current is {})",
+                version, FORMAT_VERSION
+            ),
+        )),
+        None => Err(UtreexoError::from("Empty buffer")),
     }
 }
 
-// Utility functions
-#[wasm_bindgen]
-pub fn version() -> String {
+// Leaves buffered by WasmStump::build_from_source between appends
+const SOURCE_CHUNK: usize = 1024;
+
+// Full copy of a forest for WasmStump::apply_random_ops, since rustreexo's pollard and
+// proof updates break down after some churn. Trees are kept uncollapsed: a deleted
+// leaf stays in as empty and hashing skips it, which gives the same hashes as moving
+// its sibling up. The stump's own trees are opaque roots.
+enum ShadowNode {
+    Leaf(BitcoinNodeHash),
+    Branch(Box<ShadowNode>, Box<ShadowNode>, BitcoinNodeHash),
+}
+
+impl ShadowNode {
+    fn hash(&self) -> BitcoinNodeHash {
+        match self {
+            ShadowNode::Leaf(hash) | ShadowNode::Branch(_, _, hash) => *hash,
+        }
+    }
+
+    fn branch(left: ShadowNode, right: ShadowNode) -> ShadowNode {
+        let hash = match (left.hash(), right.hash()) {
+            (left, right) if right.is_empty() => left,
+            (left, right) if left.is_empty() => right,
+            (left, right) => BitcoinNodeHash::parent_hash(&left, &right),
+        };
+        ShadowNode::Branch(Box::new(left), Box::new(right), hash)
+    }
+
+    // Empties `leaf`, returning whether it was found
+    fn delete(&mut self, leaf: &BitcoinNodeHash) -> bool {
+        match self {
+            ShadowNode::Leaf(hash) if hash == leaf => {
+                *hash = BitcoinNodeHash::Empty;
+                true
+            }
+            ShadowNode::Leaf(_) => false,
+            ShadowNode::Branch(left, right, hash) => {
+                if !left.delete(leaf) && !right.delete(leaf) {
+                    return false;
+                }
+                *self = ShadowNode::branch(
+                    std::mem::replace(left, ShadowNode::Leaf(*hash)),
+                    std::mem::replace(right, ShadowNode::Leaf(*hash)),
+                );
+                true
+            }
+        }
+    }
+
+    // Descends to `leaf` from `pos`, skipping the levels whose other side is empty as
+    // those were collapsed, and returns its position with the siblings on the way down
+    fn path(
+        &self,
+        leaf: &BitcoinNodeHash,
+        pos: u64,
+        forest_rows: u8,
+        siblings: &mut Vec<BitcoinNodeHash>,
+    ) -> Option<u64> {
+        match self {
+            ShadowNode::Leaf(hash) => (hash == leaf).then_some(pos),
+            ShadowNode::Branch(left, right, _) => [(left, right, 0), (right, left, 1)]
+                .into_iter()
+                .find_map(|(child, other, side)| {
+                    if other.hash().is_empty() {
+                        return child.path(leaf, pos, forest_rows, siblings);
+                    }
+                    siblings.push(other.hash());
+                    let found = child.path(
+                        leaf,
+                        left_child(pos, forest_rows) | side,
+                        forest_rows,
+                        siblings,
+                    );
+                    if found.is_none() {
+                        siblings.pop();
+                    }
+                    found
+                }),
+        }
+    }
+}
+
+struct ShadowForest {
+    leaves: u64,
+    // Biggest tree first, like Stump roots
+    trees: Vec<ShadowNode>,
+}
+
+impl ShadowForest {
+    fn new(stump: &Stump) -> ShadowForest {
+        ShadowForest {
+            leaves: stump.leaves,
+            trees: stump
+                .roots
+                .iter()
+                .map(|root| ShadowNode::Leaf(*root))
+                .collect(),
+        }
+    }
+
+    fn prove(&self, leaves: &[BitcoinNodeHash]) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+        let forest_rows = tree_rows(self.leaves);
+        let rows = (0..64u8).rev().filter(|row| self.leaves >> row & 1 == 1);
+        let proofs = leaves
+            .iter()
+            .map(|leaf| {
+                rows.clone()
+                    .zip(&self.trees)
+                    .find_map(|(row, tree)| {
+                        let mut siblings = Vec::new();
+                        let root = root_position(self.leaves, row, forest_rows);
+                        let pos = tree.path(leaf, root, forest_rows, &mut siblings)?;
+                        siblings.reverse();
+                        Some(Proof::new(vec![pos], siblings))
+                    })
+                    .ok_or_else(|| {
+                        UtreexoError::from(format!("Leaf {} is not in the forest", leaf))
+                    })
+            })
+            .collect::<Result<Vec<_>, UtreexoError>>()?;
+        merge_proofs_inner(&proofs, self.leaves)
+    }
+
+    // Same steps as Stump::modify: deletions first, then each addition merging with
+    // the roots of the trees it completes
+    fn modify(&mut self, add_hashes: &[BitcoinNodeHash], del_hashes: &[BitcoinNodeHash]) {
+        for leaf in del_hashes {
+            self.trees.iter_mut().any(|tree| tree.delete(leaf));
+        }
+        for hash in add_hashes {
+            let mut node = ShadowNode::Leaf(*hash);
+            let mut row = 0;
+            while self.leaves >> row & 1 == 1 {
+                if let Some(root) = self.trees.pop() {
+                    node = ShadowNode::branch(root, node);
+                }
+                row += 1;
+            }
+            self.trees.push(node);
+            self.leaves += 1;
+        }
+    }
+}
+
+// splitmix64, enough to drive WasmStump::apply_random_ops reproducibly on every target
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn hash(&mut self) -> BitcoinNodeHash {
+        let mut bytes = [0u8; 32];
+        for chunk in bytes.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next().to_le_bytes());
+        }
+        BitcoinNodeHash::new(bytes)
+    }
+}
+
+fn js_error_message(error: &JsValue) -> String {
+    match wasm_bindgen::JsCast::dyn_ref::<js_sys::Error>(error) {
+        Some(error) => error.message().into(),
+        None => error.as_string().unwrap_or_else(|| format!("{:?}", error)),
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, UtreexoError> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))?;
+    js_sys::JSON::parse(&json)
+        .map_err(|_| UtreexoError::from("Failed to convert JSON to a JS value"))
+}
+
+// JSON form of a WasmStump: rustreexo's own fields plus the sequence, which JSON
+// written before it existed lacks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StumpRecord {
+    #[serde(flatten)]
+    stump: Stump,
+    #[serde(default)]
+    sequence: u64,
+}
+
+// Plain JSON form of an accumulator for postMessage and friends, with hashes and
+// leaf data as lowercase hex so it stays readable when logged. Roots are in the
+// order each type's `roots` returns them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TransferableRecord {
+    Stump {
+        leaves: u64,
+        roots: Vec<String>,
+        #[serde(default)]
+        sequence: u64,
+    },
+    Pollard {
+        leaves: u64,
+        roots: Vec<String>,
+        remembered: Vec<TransferableLeaf>,
+    },
+}
+
+// A remembered pollard leaf with the proof hashes for its position, ascending, and
+// the caller data attached to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TransferableLeaf {
+    position: u64,
+    hash: String,
+    proof: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<String>,
+}
+
+fn parse_transferable(json_str: &str) -> Result<TransferableRecord, UtreexoError> {
+    serde_json::from_str(json_str).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse transferable JSON: {}", e),
+        )
+    })
+}
+
+// Display prints Empty (a deleted tree's root) as "empty", which doesn't parse back,
+// so transferable JSON writes it as all zeros and reads all zeros as Empty
+fn transferable_hex(hash: &BitcoinNodeHash) -> String {
+    match hash {
+        BitcoinNodeHash::Some(_) => hash.to_string(),
+        _ => "0".repeat(64),
+    }
+}
+
+fn parse_transferable_hashes(hexes: &[String]) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    Ok(parse_hex_hashes(hexes)?
+        .into_iter()
+        .map(|hash| match *hash == [0u8; 32] {
+            true => BitcoinNodeHash::empty(),
+            false => hash,
+        })
+        .collect())
+}
+
+fn bytes_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_bytes_hex(hex: &str) -> Result<Vec<u8>, UtreexoError> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return Err(UtreexoError::from(format!("Invalid hex data: {}", hex)));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|e| UtreexoError::from(format!("Invalid hex data: {}", e)))
+        })
+        .collect()
+}
+
+// One entry of an ordered add/delete log, as stored by callers for rebuilds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OpLogEntry {
+    #[serde(default)]
+    add: Vec<String>,
+    #[serde(default)]
+    del: Vec<String>,
+    proof: Proof<BitcoinNodeHash>,
+}
+
+// Update proof taking a stump from one state to another: the op log between them with
+// add-only ops folded into the op before them, which gives the same result as a
+// modify adds after deleting. Deletions can't be merged, as each one moves leaves
+// the next proof was built against.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UpdateProofRecord {
+    from_leaves: u64,
+    from_state: String,
+    // Kept in rustreexo's form, as the hex one can't hold empty roots
+    to: Stump,
+    steps: Vec<OpLogEntry>,
+}
+
+fn fold_additions(ops: Vec<OpLogEntry>) -> Vec<OpLogEntry> {
+    let mut steps: Vec<OpLogEntry> = Vec::with_capacity(ops.len());
+    for op in ops {
+        match steps.last_mut() {
+            Some(last) if op.del.is_empty() && op.proof.targets.is_empty() => {
+                last.add.extend(op.add)
+            }
+            _ => steps.push(op),
+        }
+    }
+    steps
+}
+
+// JSON form of a WasmUpdate: the op plus the stump it was applied to, which is
+// enough to recompute the UpdateData (rustreexo doesn't serialize it)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct UpdateRecord {
+    prev: Stump,
+    #[serde(flatten)]
+    op: OpLogEntry,
+}
+
+// One hop of WasmPollard::merkle_path; the root's entry has no sibling
+#[derive(Debug, Clone, Serialize)]
+struct PathStep {
+    hash: String,
+    sibling: Option<String>,
+    is_left: Option<bool>,
+}
+
+// Result of WasmStump::preview_modify
+#[derive(Debug, Clone, Serialize)]
+struct ModifyPreview {
+    roots_before: Vec<String>,
+    roots_after: Vec<String>,
+    merged_ranges: Vec<MergedRange>,
+}
+
+// Leaves [start, end) that make up a tree which only exists after the modify
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct MergedRange {
+    start: u64,
+    end: u64,
+    row: u8,
+}
+
+// Leaf ranges of a forest's trees, biggest tree first like `roots`
+fn tree_ranges(num_leaves: u64) -> Vec<MergedRange> {
+    let mut start = 0;
+    (0..64u8)
+        .rev()
+        .filter(|row| num_leaves >> row & 1 == 1)
+        .map(|row| {
+            let range = MergedRange {
+                start,
+                end: start + (1 << row),
+                row,
+            };
+            start = range.end;
+            range
+        })
+        .collect()
+}
+
+fn parse_ops(ops_json: &str) -> Result<Vec<OpLogEntry>, UtreexoError> {
+    serde_json::from_str(ops_json).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse operations JSON: {}", e),
+        )
+    })
+}
+
+fn parse_hex_hashes(hexes: &[String]) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    hexes
+        .iter()
+        .map(|hex| {
+            BitcoinNodeHash::from_str(hex).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    format!("Invalid hash: {}", e),
+                )
+            })
+        })
+        .collect()
+}
+
+// Forest position math, mirroring rustreexo's (crate-private) util module
+fn tree_rows(num_leaves: u64) -> u8 {
+    if num_leaves == 0 {
+        return 0;
+    }
+    (64 - (num_leaves - 1).leading_zeros()) as u8
+}
+
+fn detect_row(pos: u64, forest_rows: u8) -> u8 {
+    let mut marker: u64 = 1 << forest_rows;
+    let mut row: u8 = 0;
+    while pos & marker != 0 {
+        marker >>= 1;
+        row += 1;
+    }
+    row
+}
+
+fn parent(pos: u64, forest_rows: u8) -> u64 {
+    (pos >> 1) | (1 << forest_rows)
+}
+
+fn left_child(pos: u64, forest_rows: u8) -> u64 {
+    (pos << 1) & ((2 << forest_rows) - 1)
+}
+
+fn root_position(num_leaves: u64, row: u8, forest_rows: u8) -> u64 {
+    let mask = (2 << forest_rows) - 1;
+    let before = num_leaves & (mask << (row + 1));
+    let shifted = (before >> row) | (mask << (forest_rows + 1 - row));
+    shifted & mask
+}
+
+fn is_root_position(pos: u64, num_leaves: u64, forest_rows: u8) -> bool {
+    let row = detect_row(pos, forest_rows);
+    num_leaves & (1 << row) != 0 && root_position(num_leaves, row, forest_rows) == pos
+}
+
+// Reads rustreexo's pollard encoding however its bytes are split up, handing out
+// every stored node with its position as soon as it's complete. Pollard's nieces are
+// its sibling's children (a root's are its own), written depth first, so only the
+// current item and the nodes still due on the path being read are kept. The bytes may
+// be untrusted, so the leaf count, root markers and tree depth are checked before any
+// position math runs on them.
+#[derive(Default)]
+struct NodeDecoder {
+    leaves: Option<u64>,
+    markers_read: u8,
+    // Nodes still to read, the next one last: position, the node whose children its
+    // nieces are, and row
+    due: Vec<(u64, u64, u8)>,
+    // The leaf count, root marker or node read so far
+    item: Vec<u8>,
+}
+
+impl NodeDecoder {
+    fn is_done(&self) -> bool {
+        self.markers_read == 64 && self.due.is_empty()
+    }
+
+    // Takes bytes until the encoding is complete, returning how many it used
+    fn push(
+        &mut self,
+        bytes: &[u8],
+        on_node: &mut impl FnMut(u64, BitcoinNodeHash),
+    ) -> Result<usize, String> {
+        let mut used = 0;
+        while !self.is_done() {
+            let want = self.item_len()?;
+            if self.item.len() < want {
+                let take = (want - self.item.len()).min(bytes.len() - used);
+                self.item.extend_from_slice(&bytes[used..used + take]);
+                used += take;
+                if self.item.len() < want {
+                    break;
+                }
+                // A node's length is only known once its hash tag is in
+                continue;
+            }
+            self.finish_item(on_node)?;
+            self.item.clear();
+        }
+        Ok(used)
+    }
+
+    fn item_len(&self) -> Result<usize, String> {
+        if self.leaves.is_none() {
+            return Ok(8);
+        }
+        if self.due.is_empty() {
+            return Ok(1);
+        }
+        // The is_leaf byte, then the hash's tag and, for a real hash, its 32 bytes
+        match self.item.get(1) {
+            None | Some(0) | Some(1) => Ok(2),
+            Some(2) => Ok(34),
+            Some(tag) => Err(format!("unexpected hash tag {}", tag)),
+        }
+    }
+
+    fn finish_item(
+        &mut self,
+        on_node: &mut impl FnMut(u64, BitcoinNodeHash),
+    ) -> Result<(), String> {
+        let leaves = match self.leaves {
+            Some(leaves) => leaves,
+            None => {
+                let mut leaves = [0u8; 8];
+                leaves.copy_from_slice(&self.item);
+                let leaves = u64::from_be_bytes(leaves);
+                check_leaves(leaves).map_err(|e| e.message)?;
+                self.leaves = Some(leaves);
+                return Ok(());
+            }
+        };
+        let forest_rows = tree_rows(leaves);
+
+        match self.due.pop() {
+            None => {
+                let row = self.markers_read;
+                self.markers_read += 1;
+                if self.item[0] == 1 {
+                    if leaves >> row & 1 == 0 {
+                        return Err(format!("{} leaves have no tree on row {}", leaves, row));
+                    }
+                    let pos = root_position(leaves, row, forest_rows);
+                    self.due.push((pos, pos, row));
+                }
+            }
+            Some((pos, niece_parent, row)) => {
+                let hash =
+                    BitcoinNodeHash::read(&mut &self.item[1..]).map_err(|e| e.to_string())?;
+                on_node(pos, hash);
+                if self.item[0] != 1 {
+                    if row == 0 {
+                        return Err(format!("node {} on the bottom row has children", pos));
+                    }
+                    let left = left_child(niece_parent, forest_rows);
+                    self.due.push((left | 1, left, row - 1));
+                    self.due.push((left, left | 1, row - 1));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Sink running what rustreexo writes into it through a NodeDecoder, for walking a
+// pollard's nodes without holding its encoding
+struct NodeSink<F> {
+    decoder: NodeDecoder,
+    on_node: F,
+    written: usize,
+}
+
+impl<F: FnMut(u64, BitcoinNodeHash)> Write for NodeSink<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.decoder
+            .push(buf, &mut self.on_node)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Pairs each stump root with the row of its tree, biggest tree first like `roots`
+fn roots_by_row(stump: &Stump) -> Vec<(u8, BitcoinNodeHash)> {
+    (0..64u8)
+        .rev()
+        .filter(|row| stump.leaves >> row & 1 == 1)
+        .zip(stump.roots.iter().copied())
+        .collect()
+}
+
+// Whether `pos` is a node of one of the trees, i.e. climbing from it reaches a root
+fn in_forest(pos: u64, num_leaves: u64, forest_rows: u8) -> bool {
+    // Forests that tall overflow the position math; no real accumulator gets there
+    if forest_rows >= 63 || pos >= (2 << forest_rows) - 1 {
+        return false;
+    }
+
+    let mut pos = pos;
+    for _ in detect_row(pos, forest_rows)..=forest_rows {
+        if is_root_position(pos, num_leaves, forest_rows) {
+            return true;
+        }
+        pos = parent(pos, forest_rows);
+    }
+    false
+}
+
+// Positions whose hashes a batch proof for `targets` has to carry. Siblings that
+// are themselves targets or computed along a shared path are left out.
+fn proof_positions(targets: &[u64], num_leaves: u64) -> Vec<u64> {
+    let forest_rows = tree_rows(num_leaves);
+    let mut positions = BTreeSet::new();
+    let mut known: HashSet<u64> = targets.iter().copied().collect();
+    let mut computed = targets.to_vec();
+
+    let mut i = 0;
+    while i < computed.len() {
+        let pos = computed[i];
+        i += 1;
+        if is_root_position(pos, num_leaves, forest_rows) {
+            continue;
+        }
+
+        let sibling = pos ^ 1;
+        if known.contains(&sibling) {
+            positions.remove(&pos);
+        } else {
+            positions.insert(sibling);
+        }
+
+        let parent = parent(pos, forest_rows);
+        if known.insert(parent) {
+            computed.push(parent);
+        }
+    }
+
+    positions.into_iter().collect()
+}
+
+// Splits a proof's wire size over its targets: each target pays its own 8 byte
+// position, a hash (33 bytes with its tag) is shared equally by the targets whose
+// paths it is hashed with, and the two 8 byte length words plus any hashes past the
+// needed ones are shared by all. The shares add up to the wire size.
+fn proof_byte_shares(proof: &Proof<BitcoinNodeHash>, num_leaves: u64) -> Vec<f64> {
+    let count = proof.targets.len();
+    if count == 0 {
+        return Vec::new();
+    }
+    let forest_rows = tree_rows(num_leaves);
+    let positions = proof_positions(&proof.targets, num_leaves);
+    let mut shares = vec![8.0; count];
+
+    let wire_len = |hash: &BitcoinNodeHash| {
+        let mut counter = ByteCounter(0);
+        let _ = hash.write(&mut counter);
+        counter.0 as f64
+    };
+
+    for (&pos, hash) in positions.iter().zip(&proof.hashes) {
+        let row = detect_row(pos, forest_rows);
+        let users: Vec<usize> = (0..count)
+            .filter(|&i| {
+                let ancestor = (0..row).fold(proof.targets[i], |p, _| parent(p, forest_rows));
+                ancestor == pos ^ 1
+            })
+            .collect();
+        for &i in &users {
+            shares[i] += wire_len(hash) / users.len() as f64;
+        }
+    }
+
+    let unattributed: f64 = proof
+        .hashes
+        .iter()
+        .skip(positions.len())
+        .map(wire_len)
+        .sum();
+    let overhead = (16.0 + unattributed) / count as f64;
+    shares.iter_mut().for_each(|share| *share += overhead);
+    shares
+}
+
+// Hashes a proof up to the roots of the trees it touches, like Proof::calculate_hashes
+// (crate-private upstream) does, with parent hashes coming from `parent_hash`. Roots
+// come back with their positions, ascending.
+fn calculate_roots(
+    proof: &Proof<BitcoinNodeHash>,
+    del_hashes: &[BitcoinNodeHash],
+    num_leaves: u64,
+    parent_hash: &mut impl FnMut(&BitcoinNodeHash, &BitcoinNodeHash) -> BitcoinNodeHash,
+) -> Result<Vec<(u64, BitcoinNodeHash)>, String> {
+    let forest_rows = tree_rows(num_leaves);
+    let positions = proof_positions(&proof.targets, num_leaves);
+    if del_hashes.len() != proof.targets.len() {
+        return Err(format!(
+            "{} hashes for {} targets",
+            del_hashes.len(),
+            proof.targets.len()
+        ));
+    }
+    // Extra hashes are ignored like upstream does (prove_single adds one for a
+    // leaf that is its own root)
+    if proof.hashes.len() < positions.len() {
+        return Err(format!(
+            "proof has {} hashes but its targets need {}",
+            proof.hashes.len(),
+            positions.len()
+        ));
+    }
+
+    let mut provided: Vec<(u64, BitcoinNodeHash)> = proof
+        .targets
+        .iter()
+        .copied()
+        .zip(del_hashes.iter().copied())
+        .chain(positions.into_iter().zip(proof.hashes.iter().copied()))
+        .collect();
+    provided.sort();
+
+    // Parents come out in ascending order, so merging the two queues keeps every
+    // node next to its sibling
+    let mut computed: Vec<(u64, BitcoinNodeHash)> = Vec::with_capacity(provided.len());
+    let (mut computed_index, mut provided_index) = (0, 0);
+    let mut next = |computed: &[(u64, BitcoinNodeHash)]| match (
+        computed.get(computed_index),
+        provided.get(provided_index),
+    ) {
+        (Some(c), Some(p)) if c.0 < p.0 => {
+            computed_index += 1;
+            Some(*c)
+        }
+        (_, Some(p)) => {
+            provided_index += 1;
+            Some(*p)
+        }
+        (Some(c), None) => {
+            computed_index += 1;
+            Some(*c)
+        }
+        (None, None) => None,
+    };
+
+    let mut roots = Vec::new();
+    while let Some((pos, hash)) = next(&computed) {
+        if is_root_position(pos, num_leaves, forest_rows) {
+            roots.push((pos, hash));
+            continue;
+        }
+
+        let (sibling_pos, sibling_hash) =
+            next(&computed).ok_or(format!("Missing sibling for {}", pos))?;
+        if sibling_pos != pos | 1 {
+            return Err(format!("Missing sibling for {}", pos));
+        }
+
+        computed.push((parent(pos, forest_rows), parent_hash(&hash, &sibling_hash)));
+    }
+
+    Ok(roots)
+}
+
+thread_local! {
+    static HEX_UPPERCASE: Cell<bool> = Cell::new(false);
+}
+
+// Roots flattened into 8 little-endian u32 words each, ready for a GPU buffer
+fn root_words(roots: &[BitcoinNodeHash]) -> Vec<u32> {
+    roots
+        .iter()
+        .flat_map(|root| {
+            root.chunks_exact(4)
+                .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+// Hex of a hash as handed to JS, in the casing picked with set_hex_uppercase. JSON
+// and binary formats are unaffected.
+fn hex(hash: &BitcoinNodeHash) -> String {
+    let hex = hash.to_string();
+    match hash {
+        BitcoinNodeHash::Some(_) if HEX_UPPERCASE.with(Cell::get) => hex.to_ascii_uppercase(),
+        _ => hex,
+    }
+}
+
+// Hash wrapper for WASM
+#[wasm_bindgen]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hash {
+    inner: BitcoinNodeHash,
+}
+
+#[wasm_bindgen]
+impl Hash {
+    #[wasm_bindgen(constructor)]
+    pub fn new(hex: &str) -> Result<Hash, UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(hex).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        Ok(Hash { inner: hash })
+    }
+
+    #[wasm_bindgen]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Hash, UtreexoError> {
+        if bytes.len() != 32 {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                "Hash must be exactly 32 bytes",
+            ));
+        }
+
+        let mut array = [0u8; 32];
+        array.copy_from_slice(bytes);
+        Ok(Hash {
+            inner: BitcoinNodeHash::new(array),
+        })
+    }
+
+    // Deterministic test hash: bytes 0..8 are n in little-endian, bytes 8..32 are zero
+    #[wasm_bindgen]
+    pub fn from_u64(n: u64) -> Hash {
+        let mut array = [0u8; 32];
+        array[..8].copy_from_slice(&n.to_le_bytes());
+        Hash {
+            inner: BitcoinNodeHash::new(array),
+        }
+    }
+
+    // Leaf hash of a UTXO from its serialized leaf data (block hash, outpoint, height
+    // and coinbase code, amount, script), see leaf_hash
+    #[wasm_bindgen]
+    pub fn from_leaf_data(data: &[u8]) -> Hash {
+        Hash {
+            inner: leaf_hash(data),
+        }
+    }
+
+    // The node hash standing in for empty positions, e.g. roots of deleted trees
+    #[wasm_bindgen]
+    pub fn empty() -> Hash {
+        Hash {
+            inner: BitcoinNodeHash::empty(),
+        }
+    }
+
+    // Also true for a hash parsed from all zeros, which rustreexo treats the same
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        *self.inner == [0u8; 32]
+    }
+
+    #[wasm_bindgen]
+    pub fn to_hex(&self) -> String {
+        hex(&self.inner)
+    }
+
+    // Abbreviated hex like `a1b2…f9e0`; the full hex is returned when the two parts
+    // would cover it anyway
+    #[wasm_bindgen]
+    pub fn to_short(&self, prefix_len: usize, suffix_len: usize) -> String {
+        let hex = self.to_hex();
+        let prefix_len = prefix_len.min(hex.len());
+        let suffix_len = suffix_len.min(hex.len() - prefix_len);
+        if prefix_len + suffix_len >= hex.len() {
+            return hex;
+        }
+
+        format!("{}…{}", &hex[..prefix_len], &hex[hex.len() - suffix_len..])
+    }
+
+    #[wasm_bindgen]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.inner.as_ref().to_vec()
+    }
+
+    // The *_reversed variants read and write bytes in reverse, the order block
+    // explorers display txids in; everything else uses internal order
+    #[wasm_bindgen]
+    pub fn from_bytes_reversed(bytes: &[u8]) -> Result<Hash, UtreexoError> {
+        let mut hash = Hash::from_bytes(bytes)?;
+        hash.reverse();
+        Ok(hash)
+    }
+
+    #[wasm_bindgen]
+    pub fn to_bytes_reversed(&self) -> Vec<u8> {
+        self.reversed().to_bytes()
+    }
+
+    #[wasm_bindgen]
+    pub fn from_hex_reversed(hex: &str) -> Result<Hash, UtreexoError> {
+        let mut hash = Hash::new(hex)?;
+        hash.reverse();
+        Ok(hash)
+    }
+
+    #[wasm_bindgen]
+    pub fn to_hex_reversed(&self) -> String {
+        self.reversed().to_hex()
+    }
+
+    #[wasm_bindgen]
+    pub fn parent_hash(left: &Hash, right: &Hash) -> Hash {
+        Hash {
+            inner: BitcoinNodeHash::parent_hash(&left.inner, &right.inner),
+        }
+    }
+
+    // Comparisons go by the 32 bytes in internal order, so the empty hash equals
+    // all zeros
+    #[wasm_bindgen]
+    pub fn equals(&self, other: &Hash) -> bool {
+        *self.inner == *other.inner
+    }
+
+    // -1, 0 or 1 as `self` sorts before, with or after `other`, for Array.sort
+    #[wasm_bindgen]
+    pub fn compare(&self, other: &Hash) -> i32 {
+        match (*self.inner).cmp(&*other.inner) {
+            std::cmp::Ordering::Less => -1,
+            std::cmp::Ordering::Equal => 0,
+            std::cmp::Ordering::Greater => 1,
+        }
+    }
+
+    // The first 4 bytes as a little-endian i32, the same across sessions. Equal hashes
+    // get equal codes, but different ones can collide, so confirm with equals.
+    #[wasm_bindgen]
+    pub fn hash_code(&self) -> i32 {
+        i32::from_le_bytes([self.inner[0], self.inner[1], self.inner[2], self.inner[3]])
+    }
+}
+
+impl Hash {
+    fn reversed(&self) -> Hash {
+        let mut hash = Hash { inner: self.inner };
+        hash.reverse();
+        hash
+    }
+
+    fn reverse(&mut self) {
+        let mut bytes = *self.inner;
+        bytes.reverse();
+        self.inner = BitcoinNodeHash::new(bytes);
+    }
+}
+
+// Leaf hashes are domain separated from parent hashes the way utreexod and floresta
+// do it: sha512_256(tag || tag || data), with tag = sha512("UtreexoV1")
+fn leaf_hash(data: &[u8]) -> BitcoinNodeHash {
+    let tag = sha512::Hash::hash(b"UtreexoV1");
+    let mut engine = sha512_256::Hash::engine();
+    engine.input(tag.as_byte_array());
+    engine.input(tag.as_byte_array());
+    engine.input(data);
+    BitcoinNodeHash::new(sha512_256::Hash::from_engine(engine).to_byte_array())
+}
+
+static LIVE_STUMPS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_POLLARDS: AtomicUsize = AtomicUsize::new(0);
+
+// Counts the instances of the wrapper holding it, for wasm_memory_stats. Only values
+// handed to JS carry one; scratch work runs on plain Stump and Pollard values, so
+// their drops don't log releases.
+#[derive(Debug)]
+struct Live(&'static AtomicUsize, &'static str);
+
+impl Live {
+    fn stump() -> Live {
+        Live::new(&LIVE_STUMPS, "WasmStump")
+    }
+
+    fn pollard() -> Live {
+        Live::new(&LIVE_POLLARDS, "WasmPollard")
+    }
+
+    fn new(counter: &'static AtomicUsize, kind: &'static str) -> Live {
+        counter.fetch_add(1, Ordering::Relaxed);
+        Live(counter, kind)
+    }
+}
+
+impl Drop for Live {
+    fn drop(&mut self) {
+        let live = self.0.fetch_sub(1, Ordering::Relaxed) - 1;
+        log_release(self.1, live);
+    }
+}
+
+// free() and wasm-bindgen's weak-ref finalizer both end up dropping the value, so a
+// release can't tell a forgotten handle from a freed one; a live count that keeps
+// growing across releases can
+#[cfg(feature = "leak-log")]
+fn log_release(kind: &str, live: usize) {
+    web_sys::console::debug_1(&JsValue::from(format!(
+        "{} released, {} still live",
+        kind, live
+    )));
+}
+
+#[cfg(not(feature = "leak-log"))]
+fn log_release(_kind: &str, _live: usize) {}
+
+// Stump wrapper for WASM (lightweight accumulator)
+#[wasm_bindgen]
+pub struct WasmStump {
+    inner: Stump,
+    // Number of modifies applied, for ordering snapshots of the same chain
+    sequence: u64,
+    _live: Live,
+}
+
+impl Default for WasmStump {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl WasmStump {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmStump {
+        WasmStump {
+            inner: Stump::new(),
+            sequence: 0,
+            _live: Live::stump(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<WasmStump, UtreexoError> {
+        let record = parse_stump_record(json_str)?;
+        Ok(WasmStump {
+            inner: record.stump,
+            sequence: record.sequence,
+            _live: Live::stump(),
+        })
+    }
+
+    // Builds a stump from roots committed in a header, biggest tree first. There is
+    // one tree per set bit of `leaves`, so any other root count is rejected up front.
+    #[wasm_bindgen]
+    pub fn from_header_roots(roots: Vec<JsValue>, leaves: u64) -> Result<WasmStump, UtreexoError> {
+        let roots = parse_hashes(roots)?;
+        Ok(WasmStump {
+            inner: checked_stump(leaves, roots)?,
+            sequence: 0,
+            _live: Live::stump(),
+        })
+    }
+
+    // Same as to_json, but with hex hashes and a "type" tag; what a worker receiving
+    // the accumulator over postMessage hands to from_transferable_json
+    #[wasm_bindgen]
+    pub fn to_transferable_json(&self) -> Result<String, UtreexoError> {
+        let record = TransferableRecord::Stump {
+            leaves: self.inner.leaves,
+            roots: self.inner.roots.iter().map(transferable_hex).collect(),
+            sequence: self.sequence,
+        };
+        serde_json::to_string(&record)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    }
+
+    #[wasm_bindgen]
+    pub fn from_transferable_json(json_str: &str) -> Result<WasmStump, UtreexoError> {
+        match parse_transferable(json_str)? {
+            TransferableRecord::Stump {
+                leaves,
+                roots,
+                sequence,
+            } => Ok(WasmStump {
+                inner: checked_stump(leaves, parse_transferable_hashes(&roots)?)?,
+                sequence,
+                _live: Live::stump(),
+            }),
+            TransferableRecord::Pollard { .. } => Err(UtreexoError::from(
+                "Expected a transferable stump but got a pollard",
+            )),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, UtreexoError> {
+        let record = StumpRecord {
+            stump: self.inner.clone(),
+            sequence: self.sequence,
+        };
+        serde_json::to_string(&record)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    }
+
+    #[wasm_bindgen]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode_stump(&self.inner, self.sequence)
+    }
+
+    // Aliases of to_bytes / from_bytes under rustreexo's names
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmStump, UtreexoError> {
+        WasmStump::from_bytes(bytes)
+    }
+
+    // Only accepts the built-in empty hash (32 zero bytes), see check_empty_hash
+    #[wasm_bindgen]
+    pub fn configure_empty_hash(&mut self, hex: &str) -> Result<(), UtreexoError> {
+        check_empty_hash(hex)
+    }
+
+    // A stump is only roots, so all there is to check is that their count fits the
+    // leaf count
+    #[wasm_bindgen]
+    pub fn self_check(&self) -> Result<(), UtreexoError> {
+        checked_stump(self.inner.leaves, self.inner.roots.clone())?;
+        Ok(())
+    }
+
+    // Exactly to_bytes().len(), without building the buffer
+    #[wasm_bindgen]
+    pub fn serialized_size(&self) -> usize {
+        encoded_stump_len(&self.inner)
+    }
+
+    // Accepts any older format too, migrating it first
+    #[wasm_bindgen]
+    pub fn from_bytes(bytes: &[u8]) -> Result<WasmStump, UtreexoError> {
+        let bytes = migrate_inner(bytes)?;
+        let (inner, sequence) = decode_stump(&bytes)?;
+        Ok(WasmStump {
+            inner,
+            sequence,
+            _live: Live::stump(),
+        })
+    }
+
+    // Independent copy, sequence included, e.g. to try a modify and drop it on failure
+    #[wasm_bindgen(js_name = clone)]
+    pub fn clone_js(&self) -> WasmStump {
+        WasmStump {
+            inner: self.inner.clone(),
+            sequence: self.sequence,
+            _live: Live::stump(),
+        }
+    }
+
+    // Counts every modify applied since the stump was created. The leaf count only
+    // grows with additions, so this also moves on modifies that just delete.
+    #[wasm_bindgen]
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    #[wasm_bindgen]
+    pub fn num_leaves(&self) -> u64 {
+        self.inner.leaves
+    }
+
+    // Always biggest tree first, i.e. in descending order of the set bits of
+    // num_leaves; rustreexo's Stump keeps that order through every modify
+    #[wasm_bindgen]
+    pub fn roots(&self) -> Vec<JsValue> {
+        self.inner
+            .roots
+            .iter()
+            .map(|root| {
+                let hash = Hash { inner: *root };
+                JsValue::from(hash.to_hex())
+            })
+            .collect()
+    }
+
+    // Same order as `roots`, see root_words
+    #[wasm_bindgen]
+    pub fn roots_u32(&self) -> Vec<u32> {
+        root_words(&self.inner.roots)
+    }
+
+    // The `_raw` variants take and return hashes as one flat buffer of 32 bytes per
+    // hash, skipping hex entirely; empty roots come out as 32 zero bytes
+
+    #[wasm_bindgen]
+    pub fn roots_raw(&self) -> Vec<u8> {
+        flat_hashes(&self.inner.roots)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_raw(&self, proof_json: &str, hashes: &[u8]) -> Result<bool, UtreexoError> {
+        self.verify_inner(&parse_proof(proof_json)?, &parse_flat_hashes(hashes)?)
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_raw(
+        &mut self,
+        proof_json: &str,
+        add_hashes: &[u8],
+        del_hashes: &[u8],
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_flat_hashes(add_hashes)?;
+        let del_hashes = parse_flat_hashes(del_hashes)?;
+
+        self.modify_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(())
+    }
+
+    // Commitment to the leaf count and roots, see state_hash for the preimage
+    #[wasm_bindgen]
+    pub fn state_hash(&self) -> Hash {
+        Hash {
+            inner: state_hash(self.inner.leaves, &self.inner.roots),
+        }
+    }
+
+    // Fixed-forever commitment for hardcoded checkpoints, see checkpoint_digest
+    #[wasm_bindgen]
+    pub fn checkpoint_digest(&self) -> Hash {
+        Hash {
+            inner: checkpoint_digest(self.inner.leaves, &self.inner.roots),
+        }
+    }
+
+    // Rolling commitment over a sequence of states: sha512_256(prev_commit ||
+    // state_hash), with prev_commit as its 32 raw bytes (all zero before the first
+    // state). Folding this over every block's stump yields one final commitment.
+    #[wasm_bindgen]
+    pub fn chain_commit(&self, prev_commit: &str) -> Result<Hash, UtreexoError> {
+        let prev = BitcoinNodeHash::from_str(prev_commit).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid commitment: {}", e),
+            )
+        })?;
+        Ok(Hash {
+            inner: BitcoinNodeHash::parent_hash(&prev, &self.state_hash().inner),
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn contains_root(&self, hash: &str) -> Result<bool, UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        Ok(self.inner.roots.contains(&hash))
+    }
+
+    #[wasm_bindgen]
+    pub fn verify(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<bool, UtreexoError> {
+        self.verify_proof(&WasmProof::from_json(proof_json)?, hashes)
+    }
+
+    // Same as verify, without parsing the proof again
+    #[wasm_bindgen]
+    pub fn verify_proof(
+        &self,
+        proof: &WasmProof,
+        hashes: Vec<JsValue>,
+    ) -> Result<bool, UtreexoError> {
+        self.verify_inner(&proof.inner, &parse_hashes(hashes)?)
+    }
+
+    // Same as verify, but as if the stump had `leaves_override` leaves (its own count
+    // when omitted) over the same roots; meant for probing the verifier in tests
+    #[wasm_bindgen]
+    pub fn verify_with_leaves(
+        &self,
+        proof_json: &str,
+        hashes: Vec<JsValue>,
+        leaves_override: Option<u64>,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+
+        let stump = Stump {
+            leaves: leaves_override.unwrap_or(self.inner.leaves),
+            roots: self.inner.roots.clone(),
+        };
+        verify_stump(&stump, &proof, &del_hashes)
+    }
+
+    // Same as verify, but throws with the reason instead of returning false:
+    // ProofVerificationFailed when the proof doesn't reach the current roots (stale),
+    // MalformedProof / LeafCountMismatch when it can't be a proof for this stump at all
+    #[wasm_bindgen]
+    pub fn check_proof(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+        check_not_empty(self.inner.leaves, &proof)?;
+        check_leaf_count(self.inner.leaves, &proof)?;
+        explain_verify(self.inner.verify(&proof, &del_hashes))
+    }
+
+    // Same as verify, but never throws: {ok, valid, error}, where `ok` is false and
+    // `error` holds the message when the inputs couldn't be checked at all
+    #[wasm_bindgen]
+    pub fn try_verify(&self, proof_json: &str, hashes: Vec<JsValue>) -> JsValue {
+        let result = parse_proof(proof_json).and_then(|proof| {
+            let del_hashes = parse_hashes(hashes)?;
+            self.verify_inner(&proof, &del_hashes)
+        });
+
+        let (ok, valid, error) = match result {
+            Ok(valid) => (true, valid, JsValue::NULL),
+            Err(e) => (false, false, JsValue::from(e.message)),
+        };
+        let outcome = js_sys::Object::new();
+        // Setting plain properties on a fresh object can't fail
+        let _ = js_sys::Reflect::set(&outcome, &"ok".into(), &JsValue::from_bool(ok));
+        let _ = js_sys::Reflect::set(&outcome, &"valid".into(), &JsValue::from_bool(valid));
+        let _ = js_sys::Reflect::set(&outcome, &"error".into(), &error);
+        outcome.into()
+    }
+
+    // Same as verify for one leaf, with the proof from WasmPollard::prove_single_bytes
+    // (the versioned binary form) and the leaf as its 32 raw bytes
+    #[wasm_bindgen]
+    pub fn verify_single(
+        &self,
+        proof_bytes: &[u8],
+        leaf_hash: &[u8],
+    ) -> Result<bool, UtreexoError> {
+        let proof = decode_proof(&migrate_inner(proof_bytes)?)?;
+        if proof.targets.len() != 1 {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Expected a proof for a single leaf but it has {} targets",
+                    proof.targets.len()
+                ),
+            ));
+        }
+        self.verify_inner(&proof, &[hash_from_slice(leaf_hash)?])
+    }
+
+    // Same as verify, for a proof in wire form and raw 32-byte hashes
+    #[wasm_bindgen]
+    pub fn verify_wire(
+        &self,
+        proof_wire: &[u8],
+        hashes: Vec<Uint8Array>,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_wire_proof(proof_wire)?;
+        let del_hashes = parse_byte_hashes(hashes)?;
+        self.verify_inner(&proof, &del_hashes)
+    }
+
+    // Same as verify_and_roots succeeding, reusing parent hashes from `cache` across
+    // calls against this stump
+    #[wasm_bindgen]
+    pub fn verify_cached(
+        &self,
+        proof_json: &str,
+        hashes: Vec<JsValue>,
+        cache: &mut NodeCache,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+
+        self.verify_cached_inner(&proof, &del_hashes, cache)
+    }
+
+    // Returns the roots the proof hashes up to, biggest tree first; only trees holding
+    // a target are reconstructed, and each has to match the current root
+    #[wasm_bindgen]
+    pub fn verify_and_roots(
+        &self,
+        proof_json: &str,
+        hashes: Vec<JsValue>,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+
+        let roots = verify_and_roots(&self.inner, &proof, &del_hashes)?;
+        Ok(roots
+            .into_iter()
+            .map(|root| JsValue::from(hex(&root)))
+            .collect())
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_bundle(&self, bundle: &WasmProofBundle) -> Result<bool, UtreexoError> {
+        self.verify_inner(&bundle.proof, &bundle.leaves)
+    }
+
+    // Only roots are kept, so there's no way to tell an addition repeats a leaf
+    #[wasm_bindgen]
+    pub fn modify(
+        &mut self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(())
+    }
+
+    // Same as modify, but on a copy: returns the modified stump and leaves this one
+    // as it was, for immutable state updates
+    #[wasm_bindgen]
+    pub fn apply(
+        &self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<WasmStump, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        let mut next = self.inner.clone();
+        modify_stump(&mut next, &proof, &add_hashes, &del_hashes)?;
+        Ok(WasmStump {
+            inner: next,
+            sequence: self.sequence + 1,
+            _live: Live::stump(),
+        })
+    }
+
+    // Same as modify, but only commits when the resulting roots (biggest tree first,
+    // like `roots`) are exactly `expected_roots`, e.g. the ones a header commits to.
+    // On any failure the stump is left as it was.
+    #[wasm_bindgen]
+    pub fn apply_and_check(
+        &mut self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+        expected_roots: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+        let expected_roots = parse_hashes(expected_roots)?;
+
+        self.apply_and_check_inner(&proof, &add_hashes, &del_hashes, &expected_roots)
+    }
+
+    // Same as modify, returning the position each deleted leaf had, in the same order
+    // as `del_hashes`
+    #[wasm_bindgen]
+    pub fn modify_with_deletions(
+        &mut self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<Vec<u64>, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(proof.targets)
+    }
+
+    // Appends leaves without deleting any, returning the position each one got, in
+    // the same order as `hashes`
+    #[wasm_bindgen]
+    pub fn add(&mut self, hashes: Vec<JsValue>) -> Result<Vec<u64>, UtreexoError> {
+        let add_hashes = parse_hashes(hashes)?;
+        self.add_inner(&add_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn apply_block(&mut self, update: &WasmBlockUpdate) -> Result<(), UtreexoError> {
+        self.modify_inner(&update.proof, &update.added_hashes(), &update.deletions)?;
+        Ok(())
+    }
+
+    // Deletes the bundle's leaves and adds `add_hashes`
+    #[wasm_bindgen]
+    pub fn modify_bundle(
+        &mut self,
+        bundle: &WasmProofBundle,
+        add_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let add_hashes = parse_hashes(add_hashes)?;
+
+        self.modify_inner(&bundle.proof, &add_hashes, &bundle.leaves)?;
+        Ok(())
+    }
+
+    // Same as modify, but hands back a WasmUpdate describing what was applied
+    #[wasm_bindgen]
+    pub fn modify_with_update(
+        &mut self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<WasmUpdate, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let added = parse_hashes(add_hashes)?;
+        let deleted = parse_hashes(del_hashes)?;
+
+        let prev = self.inner.clone();
+        let data = self.modify_inner(&proof, &added, &deleted)?;
+
+        Ok(WasmUpdate {
+            prev,
+            added,
+            deleted,
+            proof,
+            data,
+        })
+    }
+
+    // Reverts `update`, which has to be the last one applied to this stump (as for a
+    // reorg of the tip block). That update moved the sequence on by one, so undoing
+    // it steps the sequence back to what it was before.
+    #[wasm_bindgen]
+    pub fn undo(&mut self, update: &WasmUpdate) -> Result<(), UtreexoError> {
+        let (after, _) = update
+            .prev
+            .modify(&update.added, &update.deleted, &update.proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to replay update: {}", e)))?;
+        if after != self.inner {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::RootsMismatch,
+                "Failed to undo: the update isn't the last one applied to this stump",
+            ));
+        }
+
+        self.inner.undo(update.prev.clone());
+        self.sequence = self.sequence.saturating_sub(1);
+        Ok(())
+    }
+
+    // Same as modify, but returns one flag per new root telling whether it differs
+    // from the root the same tree had before
+    #[wasm_bindgen]
+    pub fn modify_with_changes(
+        &mut self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        let changed = self.modify_with_changes_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(changed.into_iter().map(JsValue::from_bool).collect())
+    }
+
+    // What modify would do, worked out on a copy: the roots before and after, and the
+    // leaf ranges of the trees that additions merge into existence
+    #[wasm_bindgen]
+    pub fn preview_modify(
+        &self,
+        proof_json: &str,
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<JsValue, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        to_js(&self.preview_modify_inner(&proof, &add_hashes, &del_hashes)?)
+    }
+
+    // The `_bytes` variants take the proof JSON as UTF-8 bytes (e.g. straight off a
+    // fetch body) instead of a string
+
+    #[wasm_bindgen]
+    pub fn verify_bytes(
+        &self,
+        proof_json: &[u8],
+        hashes: Vec<JsValue>,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+        self.verify_inner(&proof, &del_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_cached_bytes(
+        &self,
+        proof_json: &[u8],
+        hashes: Vec<JsValue>,
+        cache: &mut NodeCache,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+
+        self.verify_cached_inner(&proof, &del_hashes, cache)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_and_roots_bytes(
+        &self,
+        proof_json: &[u8],
+        hashes: Vec<JsValue>,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+
+        let roots = verify_and_roots(&self.inner, &proof, &del_hashes)?;
+        Ok(roots
+            .into_iter()
+            .map(|root| JsValue::from(hex(&root)))
+            .collect())
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_bytes(
+        &mut self,
+        proof_json: &[u8],
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_with_update_bytes(
+        &mut self,
+        proof_json: &[u8],
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<WasmUpdate, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let added = parse_hashes(add_hashes)?;
+        let deleted = parse_hashes(del_hashes)?;
+
+        let prev = self.inner.clone();
+        let data = self.modify_inner(&proof, &added, &deleted)?;
+
+        Ok(WasmUpdate {
+            prev,
+            added,
+            deleted,
+            proof,
+            data,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_with_changes_bytes(
+        &mut self,
+        proof_json: &[u8],
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        let changed = self.modify_with_changes_inner(&proof, &add_hashes, &del_hashes)?;
+        Ok(changed.into_iter().map(JsValue::from_bool).collect())
+    }
+
+    #[wasm_bindgen]
+    pub fn preview_modify_bytes(
+        &self,
+        proof_json: &[u8],
+        add_hashes: Vec<JsValue>,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<JsValue, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let add_hashes = parse_hashes(add_hashes)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        to_js(&self.preview_modify_inner(&proof, &add_hashes, &del_hashes)?)
+    }
+
+    // Rebuilds a stump from empty by applying a JSON array of {add, del, proof} ops
+    #[wasm_bindgen]
+    pub fn replay(ops_json: &str) -> Result<WasmStump, UtreexoError> {
+        let ops = parse_ops(ops_json)?;
+
+        let mut stump = Stump::new();
+        apply_stump_ops(&mut stump, &ops)?;
+        Ok(WasmStump {
+            inner: stump,
+            sequence: ops.len() as u64,
+            _live: Live::stump(),
+        })
+    }
+
+    // Builds a stump from leaf hashes pulled from `next` until it returns null or
+    // undefined. Leaves are appended a chunk at a time, so only the accumulator and
+    // one chunk are ever held; the whole build counts as one modify.
+    #[wasm_bindgen]
+    pub fn build_from_source(next: &js_sys::Function) -> Result<WasmStump, UtreexoError> {
+        let mut stump = Stump::new();
+        let mut chunk = Vec::with_capacity(SOURCE_CHUNK);
+        loop {
+            let value = next.call0(&JsValue::NULL).map_err(|e| {
+                UtreexoError::from(format!(
+                    "Leaf source failed after {} leaves: {}",
+                    stump.leaves + chunk.len() as u64,
+                    js_error_message(&e)
+                ))
+            })?;
+            if value.is_null() || value.is_undefined() {
+                break;
+            }
+
+            chunk.extend(parse_hashes(vec![value])?);
+            if chunk.len() == SOURCE_CHUNK {
+                modify_stump(&mut stump, &Proof::default(), &chunk, &[])?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            modify_stump(&mut stump, &Proof::default(), &chunk, &[])?;
+        }
+
+        Ok(WasmStump {
+            sequence: u64::from(stump.leaves > 0),
+            inner: stump,
+            _live: Live::stump(),
+        })
+    }
+
+    // Applies `steps` random blocks of up to 3 deletions and 3 additions, the same ones
+    // for the same seed and starting state, and returns them as an {add, del, proof} op
+    // log like replay takes. Only leaves added here get deleted, since their proofs are
+    // tracked along the way. Nothing is applied unless every step is.
+    #[wasm_bindgen]
+    pub fn apply_random_ops(&mut self, seed: u64, steps: u32) -> Result<JsValue, UtreexoError> {
+        to_js(&self.apply_random_ops_inner(seed, steps)?)
+    }
+
+    // Leaf count after each op of the log, replayed from empty
+    #[wasm_bindgen]
+    pub fn replay_leaf_counts(ops_json: &str) -> Result<Vec<u64>, UtreexoError> {
+        let ops = parse_ops(ops_json)?;
+        apply_stump_ops(&mut Stump::new(), &ops)
+    }
+
+    // Whether replaying the op log on top of this stump ends at `descendant`
+    #[wasm_bindgen]
+    pub fn is_ancestor_of(
+        &self,
+        descendant: &WasmStump,
+        update_log: &str,
+    ) -> Result<bool, UtreexoError> {
+        let ops = parse_ops(update_log)?;
+
+        let mut stump = self.inner.clone();
+        apply_stump_ops(&mut stump, &ops)?;
+        Ok(stump == descendant.inner)
+    }
+
+    // Advances the stump along an update proof from compute_update_proof. It has to
+    // start at this stump's state and every step is verified while replaying; the
+    // stump is only changed once it landed on the state the proof ends at.
+    #[wasm_bindgen]
+    pub fn apply_update_proof(&mut self, update_json: &str) -> Result<(), UtreexoError> {
+        let record: UpdateProofRecord = serde_json::from_str(update_json).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidJson,
+                format!("Failed to parse update proof JSON: {}", e),
+            )
+        })?;
+        self.apply_update_proof_inner(&record)
+    }
+
+    // Whether `other` can be merged onto this stump, see merge
+    #[wasm_bindgen]
+    pub fn can_merge(&self, other: &WasmStump) -> bool {
+        self.merge_inner(other).is_ok()
+    }
+
+    // The stump this one would become if `other`'s leaves had been added after its own,
+    // with `other`'s deletions applied to them. That only works when each of `other`'s
+    // trees lands intact on a tree boundary, which holds exactly when this stump's leaf
+    // count is a multiple of the leaf count of `other`'s biggest tree. Both stumps have
+    // to cover disjoint leaves; proofs for `other`'s leaves have to be rebuilt, as
+    // their positions shift by this stump's leaf count.
+    #[wasm_bindgen]
+    pub fn merge(&self, other: &WasmStump) -> Result<WasmStump, UtreexoError> {
+        Ok(WasmStump {
+            inner: self.merge_inner(other)?,
+            sequence: 0,
+            _live: Live::stump(),
+        })
+    }
+}
+
+// Upstream verify doesn't check a leaf that is its own root against that root
+// (and overflows on positions outside the forest), so every path is checked
+// here, root included
+fn checked_remembered(
+    stump: &Stump,
+    entries: Vec<WalletEntry>,
+) -> Result<Vec<(u64, BitcoinNodeHash, Proof<BitcoinNodeHash>)>, UtreexoError> {
+    entries
+        .into_iter()
+        .map(|(hash, proof)| {
+            if proof.targets.len() != 1 || verify_and_roots(stump, &proof, &[hash]).is_err() {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::ProofVerificationFailed,
+                    format!("The proof for remembered leaf {} doesn't verify", hash),
+                ));
+            }
+            Ok((proof.targets[0], hash, proof))
+        })
+        .collect()
+}
+
+fn verify_stump(
+    stump: &Stump,
+    proof: &Proof<BitcoinNodeHash>,
+    del_hashes: &[BitcoinNodeHash],
+) -> Result<bool, UtreexoError> {
+    check_not_empty(stump.leaves, proof)?;
+    check_leaf_count(stump.leaves, proof)?;
+    // Upstream reports roots that don't match as Ok(false), not as an error
+    Ok(stump.verify(proof, del_hashes) == Ok(true))
+}
+
+// Leaves stay as they were when the modify fails
+fn modify_stump(
+    stump: &mut Stump,
+    proof: &Proof<BitcoinNodeHash>,
+    add_hashes: &[BitcoinNodeHash],
+    del_hashes: &[BitcoinNodeHash],
+) -> Result<UpdateData<BitcoinNodeHash>, UtreexoError> {
+    check_not_empty(stump.leaves, proof)?;
+    check_leaves(stump.leaves)?;
+    let leaves_after = grown_leaves(stump.leaves, add_hashes.len())?;
+    check_deletion_rows(
+        &stump.roots,
+        leaves_after,
+        add_hashes.len(),
+        del_hashes.len(),
+    )?;
+
+    check_deletion_targets("stump", del_hashes, proof)?;
+    // Proofs that can't fit this forest get MalformedProof or LeafCountMismatch
+    // here, so what upstream rejects below really is a roots mismatch
+    check_leaf_count(stump.leaves, proof)?;
+
+    // Stump::modify fails with "Missing sibling" when the proof hashes don't line
+    // up with the targets and "Invalid proof" when the computed roots aren't ours
+    let (new_stump, update_data) =
+        stump
+            .modify(add_hashes, del_hashes, proof)
+            .map_err(|e| match e.as_str() {
+                "Invalid proof" => UtreexoError::new(
+                    UtreexoErrorKind::ProofVerificationFailed,
+                    format!("Failed to modify stump: proof does not verify: {}", e),
+                ),
+                e if e.starts_with("Missing sibling") => UtreexoError::new(
+                    UtreexoErrorKind::MalformedProof,
+                    format!("Failed to modify stump: proof is malformed: {}", e),
+                ),
+                e => UtreexoError::from(format!("Failed to modify stump: {}", e)),
+            })?;
+
+    *stump = new_stump;
+    Ok(update_data)
+}
+
+// Applies the ops in order, failing at the first one that doesn't apply (the ones
+// before it stay applied), and returns the leaf count after each op
+fn apply_stump_ops(stump: &mut Stump, ops: &[OpLogEntry]) -> Result<Vec<u64>, UtreexoError> {
+    let mut leaf_counts = Vec::with_capacity(ops.len());
+    for (index, op) in ops.iter().enumerate() {
+        let applied = parse_hex_hashes(&op.add).and_then(|add_hashes| {
+            let del_hashes = parse_hex_hashes(&op.del)?;
+            modify_stump(stump, &op.proof, &add_hashes, &del_hashes)
+        });
+
+        if let Err(e) = applied {
+            return Err(UtreexoError::new(
+                e.kind,
+                format!("Operation {} failed: {}", index, e.message),
+            ));
+        }
+        leaf_counts.push(stump.leaves);
+    }
+
+    Ok(leaf_counts)
+}
+
+fn verify_and_roots(
+    stump: &Stump,
+    proof: &Proof<BitcoinNodeHash>,
+    del_hashes: &[BitcoinNodeHash],
+) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    verify_and_roots_with(stump, proof, del_hashes, &mut |left, right| {
+        BitcoinNodeHash::parent_hash(left, right)
+    })
+}
+
+fn verify_and_roots_with(
+    stump: &Stump,
+    proof: &Proof<BitcoinNodeHash>,
+    del_hashes: &[BitcoinNodeHash],
+    parent_hash: &mut impl FnMut(&BitcoinNodeHash, &BitcoinNodeHash) -> BitcoinNodeHash,
+) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+    check_not_empty(stump.leaves, proof)?;
+    check_leaf_count(stump.leaves, proof)?;
+    let forest_rows = tree_rows(stump.leaves);
+    let current: HashMap<u8, BitcoinNodeHash> = roots_by_row(stump).into_iter().collect();
+
+    let mut roots = calculate_roots(proof, del_hashes, stump.leaves, parent_hash).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::ProofVerificationFailed,
+            format!("Proof does not verify: {}", e),
+        )
+    })?;
+    roots.reverse();
+
+    roots
+        .into_iter()
+        .map(|(pos, root)| {
+            let row = detect_row(pos, forest_rows);
+            match current.get(&row) {
+                Some(expected) if *expected == root => Ok(root),
+                _ => Err(UtreexoError::new(
+                    UtreexoErrorKind::ProofVerificationFailed,
+                    format!(
+                        "Proof does not verify: computed root {} for row {} is not ours",
+                        root, row
+                    ),
+                )),
+            }
+        })
+        .collect()
+}
+
+impl WasmStump {
+    fn verify_inner(
+        &self,
+        proof: &Proof<BitcoinNodeHash>,
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<bool, UtreexoError> {
+        verify_stump(&self.inner, proof, del_hashes)
+    }
+
+    // Leaves go in at the bottom row one after another, so with no deletions the
+    // n-th added leaf lands at position `leaves + n`
+    fn add_inner(&mut self, add_hashes: &[BitcoinNodeHash]) -> Result<Vec<u64>, UtreexoError> {
+        let first = self.inner.leaves;
+        self.modify_inner(&Proof::default(), add_hashes, &[])?;
+        Ok((first..self.inner.leaves).collect())
+    }
+
+    fn modify_inner(
+        &mut self,
+        proof: &Proof<BitcoinNodeHash>,
+        add_hashes: &[BitcoinNodeHash],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<UpdateData<BitcoinNodeHash>, UtreexoError> {
+        let update_data = modify_stump(&mut self.inner, proof, add_hashes, del_hashes)?;
+        self.sequence += 1;
+        Ok(update_data)
+    }
+
+    fn apply_and_check_inner(
+        &mut self,
+        proof: &Proof<BitcoinNodeHash>,
+        add_hashes: &[BitcoinNodeHash],
+        del_hashes: &[BitcoinNodeHash],
+        expected_roots: &[BitcoinNodeHash],
+    ) -> Result<(), UtreexoError> {
+        let mut next = self.inner.clone();
+        modify_stump(&mut next, proof, add_hashes, del_hashes)?;
+
+        if next.roots != expected_roots {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::RootsMismatch,
+                format!(
+                    "Failed to modify stump: the resulting {} roots aren't the {} expected ones",
+                    next.roots.len(),
+                    expected_roots.len()
+                ),
+            ));
+        }
+
+        self.inner = next;
+        self.sequence += 1;
+        Ok(())
+    }
+
+    fn apply_update_proof_inner(&mut self, record: &UpdateProofRecord) -> Result<(), UtreexoError> {
+        let state = state_hash(self.inner.leaves, &self.inner.roots).to_string();
+        if record.from_leaves != self.inner.leaves || record.from_state != state {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::RootsMismatch,
+                "Failed to apply update proof: it starts from another state",
+            ));
+        }
+
+        let mut next = self.inner.clone();
+        apply_stump_ops(&mut next, &record.steps)?;
+        if next != record.to {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::RootsMismatch,
+                "Failed to apply update proof: the steps don't end at its final state",
+            ));
+        }
+
+        self.inner = next;
+        self.sequence += record.steps.len() as u64;
+        Ok(())
+    }
+
+    fn merge_inner(&self, other: &WasmStump) -> Result<Stump, UtreexoError> {
+        let leaves = self.inner.leaves;
+        let biggest = (0..64u8)
+            .rev()
+            .find(|row| other.inner.leaves >> row & 1 == 1);
+        if let Some(row) = biggest {
+            if leaves % (1 << row) != 0 {
+                return Err(UtreexoError::from(format!(
+                    "Can't merge: {} leaves isn't a multiple of the other stump's biggest tree of {} leaves",
+                    leaves,
+                    1u64 << row
+                )));
+            }
+        }
+        let merged_leaves = leaves.checked_add(other.inner.leaves).ok_or_else(|| {
+            UtreexoError::new(
+                UtreexoErrorKind::Overflow,
+                "Merged leaf count overflows u64",
+            )
+        })?;
+        check_leaves(merged_leaves)?;
+
+        // Merging trees like adding leaves does: equal sized trees join, the earlier
+        // one on the left, and a fully deleted tree leaves its sibling to move up
+        let mut trees = roots_by_row(&self.inner);
+        for (mut row, mut root) in roots_by_row(&other.inner) {
+            while let Some(&(top_row, top)) = trees.last() {
+                if top_row != row {
+                    break;
+                }
+                trees.pop();
+                root = if top.is_empty() {
+                    root
+                } else if root.is_empty() {
+                    top
+                } else {
+                    BitcoinNodeHash::parent_hash(&top, &root)
+                };
+                row += 1;
+            }
+            trees.push((row, root));
+        }
+
+        checked_stump(
+            merged_leaves,
+            trees.into_iter().map(|(_, root)| root).collect(),
+        )
+    }
+
+    fn verify_cached_inner(
+        &self,
+        proof: &Proof<BitcoinNodeHash>,
+        del_hashes: &[BitcoinNodeHash],
+        cache: &mut NodeCache,
+    ) -> Result<bool, UtreexoError> {
+        cache.invalidate_unless(&self.inner);
+        match verify_and_roots_with(&self.inner, proof, del_hashes, &mut |left, right| {
+            cache.parent_hash(left, right)
+        }) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind == UtreexoErrorKind::ProofVerificationFailed => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn modify_with_changes_inner(
+        &mut self,
+        proof: &Proof<BitcoinNodeHash>,
+        add_hashes: &[BitcoinNodeHash],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<Vec<bool>, UtreexoError> {
+        let old_roots: HashMap<u8, BitcoinNodeHash> =
+            roots_by_row(&self.inner).into_iter().collect();
+        self.modify_inner(proof, add_hashes, del_hashes)?;
+
+        Ok(roots_by_row(&self.inner)
+            .into_iter()
+            .map(|(row, root)| old_roots.get(&row) != Some(&root))
+            .collect())
+    }
+
+    fn preview_modify_inner(
+        &self,
+        proof: &Proof<BitcoinNodeHash>,
+        add_hashes: &[BitcoinNodeHash],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<ModifyPreview, UtreexoError> {
+        let mut after = self.inner.clone();
+        modify_stump(&mut after, proof, add_hashes, del_hashes)?;
+
+        let before_ranges = tree_ranges(self.inner.leaves);
+        Ok(ModifyPreview {
+            roots_before: self.inner.roots.iter().map(hex).collect(),
+            roots_after: after.roots.iter().map(hex).collect(),
+            merged_ranges: tree_ranges(after.leaves)
+                .into_iter()
+                .filter(|range| !before_ranges.contains(range))
+                .collect(),
+        })
+    }
+
+    fn apply_random_ops_inner(
+        &mut self,
+        seed: u64,
+        steps: u32,
+    ) -> Result<Vec<OpLogEntry>, UtreexoError> {
+        let mut rng = SplitMix64(seed);
+        let mut stump = self.inner.clone();
+        let mut shadow = ShadowForest::new(&self.inner);
+
+        let mut live = Vec::new();
+        let mut log = Vec::with_capacity(steps as usize);
+        for _ in 0..steps {
+            let deletions = rng.below(4).min(live.len() as u64);
+            let del_hashes: Vec<BitcoinNodeHash> = (0..deletions)
+                .map(|_| live.swap_remove(rng.below(live.len() as u64) as usize))
+                .collect();
+            let additions = rng.below(4) + u64::from(del_hashes.is_empty());
+            let add_hashes: Vec<BitcoinNodeHash> = (0..additions).map(|_| rng.hash()).collect();
+
+            let proof = shadow.prove(&del_hashes)?;
+            modify_stump(&mut stump, &proof, &add_hashes, &del_hashes)?;
+            shadow.modify(&add_hashes, &del_hashes);
+
+            live.extend(add_hashes.iter().copied());
+            log.push(OpLogEntry {
+                add: add_hashes.iter().map(|hash| hash.to_string()).collect(),
+                del: del_hashes.iter().map(|hash| hash.to_string()).collect(),
+                proof,
+            });
+        }
+
+        self.inner = stump;
+        self.sequence += u64::from(steps);
+        Ok(log)
+    }
+}
+
+// What a single stump modify did, for undo and proof-update flows
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmUpdate {
+    prev: Stump,
+    added: Vec<BitcoinNodeHash>,
+    deleted: Vec<BitcoinNodeHash>,
+    proof: Proof<BitcoinNodeHash>,
+    data: UpdateData<BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl WasmUpdate {
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<WasmUpdate, UtreexoError> {
+        let record: UpdateRecord = serde_json::from_str(json_str).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidJson,
+                format!("Failed to parse JSON: {}", e),
+            )
+        })?;
+        let added = parse_hex_hashes(&record.op.add)?;
+        let deleted = parse_hex_hashes(&record.op.del)?;
+
+        // Replaying the update checks that it really applies to `prev`
+        let data = modify_stump(&mut record.prev.clone(), &record.op.proof, &added, &deleted)?;
+
+        Ok(WasmUpdate {
+            prev: record.prev,
+            added,
+            deleted,
+            proof: record.op.proof,
+            data,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, UtreexoError> {
+        let record = UpdateRecord {
+            prev: self.prev.clone(),
+            op: OpLogEntry {
+                add: self.added.iter().map(|hash| hash.to_string()).collect(),
+                del: self.deleted.iter().map(|hash| hash.to_string()).collect(),
+                proof: self.proof.clone(),
+            },
+        };
+
+        serde_json::to_string(&record)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn added(&self) -> Vec<JsValue> {
+        self.added
+            .iter()
+            .map(|hash| JsValue::from(hex(hash)))
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn deleted(&self) -> Vec<JsValue> {
+        self.deleted
+            .iter()
+            .map(|hash| JsValue::from(hex(hash)))
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn deleted_positions(&self) -> Vec<u64> {
+        self.proof.targets.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn prev_num_leaves(&self) -> u64 {
+        self.prev.leaves
+    }
+
+    // Leaf positions the added hashes got; leaves only ever append at row 0
+    #[wasm_bindgen(getter)]
+    pub fn added_positions(&self) -> Vec<u64> {
+        (self.prev.leaves..self.prev.leaves + self.added.len() as u64).collect()
+    }
+
+    // Turns a proof valid before the update into one valid after it, for leaves the
+    // update didn't delete. The new targets tell where those leaves moved.
+    #[wasm_bindgen]
+    pub fn update_proof(
+        &self,
+        proof_json: &str,
+        hashes: Vec<JsValue>,
+    ) -> Result<WasmProofBundle, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let leaves = parse_hashes(hashes)?;
+        if let Some(leaf) = leaves.iter().find(|leaf| self.deleted.contains(leaf)) {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!("Failed to update proof: leaf {} was deleted", leaf),
+            ));
+        }
+
+        let (updated, leaves) = self
+            .refresh(&proof, &leaves)
+            .map_err(|e| UtreexoError::from(format!("Failed to update proof: {}", e)))?;
+        let (after, _) = self
+            .prev
+            .modify(&self.added, &self.deleted, &self.proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to replay update: {}", e)))?;
+
+        let mut bundle = WasmProofBundle::from_parts(updated, leaves)?;
+        bundle.valid_at = Some((after.leaves, state_hash(after.leaves, &after.roots)));
+        Ok(bundle)
+    }
+}
+
+impl WasmUpdate {
+    fn refresh(
+        &self,
+        proof: &Proof<BitcoinNodeHash>,
+        leaves: &[BitcoinNodeHash],
+    ) -> Result<(Proof<BitcoinNodeHash>, Vec<BitcoinNodeHash>), String> {
+        // Proof::update expects only the hashes the proof needs, no trailing extras
+        let mut current = proof.clone();
+        current
+            .hashes
+            .truncate(proof_positions(&current.targets, self.prev.leaves).len());
+
+        current.update(
+            leaves.to_vec(),
+            self.added.clone(),
+            self.proof.targets.clone(),
+            Vec::new(),
+            self.data.clone(),
+        )
+    }
+}
+
+// A parsed proof, so sync loops can hand the same proof to several calls without a
+// JSON round trip each time
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct WasmProof {
+    inner: Proof<BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl WasmProof {
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<WasmProof, UtreexoError> {
+        Ok(WasmProof {
+            inner: parse_proof(json_str)?,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, UtreexoError> {
+        serde_json::to_string(&self.inner)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn targets(&self) -> Vec<u64> {
+        self.inner.targets.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn n_targets(&self) -> usize {
+        self.inner.n_targets()
+    }
+
+    // Same as targets, but fails with MalformedProof unless they're strictly ascending.
+    // Verification doesn't care about target order, but a canonical proof has it.
+    #[wasm_bindgen]
+    pub fn sorted_targets(&self) -> Result<Vec<u64>, UtreexoError> {
+        check_sorted_targets(&self.inner.targets)?;
+        Ok(self.inner.targets.clone())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hashes(&self) -> Vec<JsValue> {
+        self.inner
+            .hashes
+            .iter()
+            .map(|hash| JsValue::from(hex(hash)))
+            .collect()
+    }
+}
+
+fn check_sorted_targets(targets: &[u64]) -> Result<(), UtreexoError> {
+    match targets.windows(2).find(|pair| pair[0] >= pair[1]) {
+        Some(pair) if pair[0] == pair[1] => Err(UtreexoError::new(
+            UtreexoErrorKind::MalformedProof,
+            format!("Target {} is repeated", pair[0]),
+        )),
+        Some(pair) => Err(UtreexoError::new(
+            UtreexoErrorKind::MalformedProof,
+            format!("Targets aren't sorted: {} comes after {}", pair[1], pair[0]),
+        )),
+        None => Ok(()),
+    }
+}
+
+// A proof together with the leaf hashes it proves, one per target in the same order.
+// Consumers should never reorder targets or hashes on their own; pass the bundle back
+// as a whole to verify_bundle / modify_bundle instead.
+#[wasm_bindgen]
+pub struct WasmProofBundle {
+    proof: Proof<BitcoinNodeHash>,
+    leaves: Vec<BitcoinNodeHash>,
+    // Accumulator state the proof was generated against, when it came from prove_bundle
+    valid_at: Option<(u64, BitcoinNodeHash)>,
+}
+
+#[wasm_bindgen]
+impl WasmProofBundle {
+    // Hashes must be given in the proof's target order
+    #[wasm_bindgen(constructor)]
+    pub fn new(proof_json: &str, hashes: Vec<JsValue>) -> Result<WasmProofBundle, UtreexoError> {
+        WasmProofBundle::from_parts(parse_proof(proof_json)?, parse_hashes(hashes)?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn proof(&self) -> Result<String, UtreexoError> {
+        serde_json::to_string(&self.proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn targets(&self) -> Vec<u64> {
+        self.proof.targets.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hashes(&self) -> Vec<JsValue> {
+        self.leaves
+            .iter()
+            .map(|hash| JsValue::from(hex(hash)))
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn valid_at_leaves(&self) -> Option<u64> {
+        self.valid_at.map(|(leaves, _)| leaves)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn valid_at_state_hash(&self) -> Option<String> {
+        self.valid_at.map(|(_, state)| hex(&state))
+    }
+}
+
+impl WasmProofBundle {
+    fn from_parts(
+        proof: Proof<BitcoinNodeHash>,
+        leaves: Vec<BitcoinNodeHash>,
+    ) -> Result<WasmProofBundle, UtreexoError> {
+        if leaves.len() != proof.targets.len() {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Failed to build proof bundle: {} hashes but the proof has {} targets",
+                    leaves.len(),
+                    proof.targets.len()
+                ),
+            ));
+        }
+        Ok(WasmProofBundle {
+            proof,
+            leaves,
+            valid_at: None,
+        })
+    }
+}
+
+// JSON form of a WasmBlockUpdate, for storing blocks to replay later
+#[derive(Debug, Serialize, Deserialize)]
+struct BlockRecord {
+    additions: Vec<AdditionRecord>,
+    deletions: Vec<String>,
+    proof: Proof<BitcoinNodeHash>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AdditionRecord {
+    hash: String,
+    #[serde(default = "remember_by_default")]
+    remember: bool,
+}
+
+fn remember_by_default() -> bool {
+    true
+}
+
+// Everything a block does to the accumulator: the leaves it adds, the leaves it
+// spends and the proof for those. Stumps ignore the remember flags.
+#[wasm_bindgen]
+pub struct WasmBlockUpdate {
+    additions: Vec<PollardAddition<BitcoinNodeHash>>,
+    deletions: Vec<BitcoinNodeHash>,
+    proof: Proof<BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl WasmBlockUpdate {
+    // Same arguments as WasmPollard::modify; deletions in the proof's target order
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        proof_json: &str,
+        additions_json: &str,
+        deletions: Vec<JsValue>,
+    ) -> Result<WasmBlockUpdate, UtreexoError> {
+        WasmBlockUpdate::from_parts(
+            parse_proof(proof_json)?,
+            parse_additions(additions_json)?,
+            parse_hashes(deletions)?,
+        )
+    }
+
+    #[wasm_bindgen]
+    pub fn from_json(json_str: &str) -> Result<WasmBlockUpdate, UtreexoError> {
+        let record: BlockRecord = serde_json::from_str(json_str).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidJson,
+                format!("Failed to parse JSON: {}", e),
+            )
+        })?;
+        let additions = record
+            .additions
+            .iter()
+            .map(|addition| {
+                let hash = BitcoinNodeHash::from_str(&addition.hash).map_err(|e| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::InvalidHash,
+                        format!("Invalid hash in addition: {}", e),
+                    )
+                })?;
+                Ok(PollardAddition {
+                    hash,
+                    remember: addition.remember,
+                })
+            })
+            .collect::<Result<_, UtreexoError>>()?;
+        let deletions = parse_hex_hashes(&record.deletions)?;
+
+        WasmBlockUpdate::from_parts(record.proof, additions, deletions)
+    }
+
+    #[wasm_bindgen]
+    pub fn to_json(&self) -> Result<String, UtreexoError> {
+        let record = BlockRecord {
+            additions: self
+                .additions
+                .iter()
+                .map(|addition| AdditionRecord {
+                    hash: addition.hash.to_string(),
+                    remember: addition.remember,
+                })
+                .collect(),
+            deletions: self.deletions.iter().map(|hash| hash.to_string()).collect(),
+            proof: self.proof.clone(),
+        };
+
+        serde_json::to_string(&record)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn proof(&self) -> Result<String, UtreexoError> {
+        serde_json::to_string(&self.proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn additions(&self) -> Vec<JsValue> {
+        self.additions
+            .iter()
+            .map(|addition| JsValue::from(hex(&addition.hash)))
+            .collect()
+    }
+
+    // Parallel to `additions`
+    #[wasm_bindgen(getter)]
+    pub fn remembered(&self) -> Vec<JsValue> {
+        self.additions
+            .iter()
+            .map(|addition| JsValue::from_bool(addition.remember))
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn deletions(&self) -> Vec<JsValue> {
+        self.deletions
+            .iter()
+            .map(|hash| JsValue::from(hex(hash)))
+            .collect()
+    }
+}
+
+impl WasmBlockUpdate {
+    fn from_parts(
+        proof: Proof<BitcoinNodeHash>,
+        additions: Vec<PollardAddition<BitcoinNodeHash>>,
+        deletions: Vec<BitcoinNodeHash>,
+    ) -> Result<WasmBlockUpdate, UtreexoError> {
+        if deletions.len() != proof.targets.len() {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Failed to build block update: {} deletions but the proof has {} targets",
+                    deletions.len(),
+                    proof.targets.len()
+                ),
+            ));
+        }
+        Ok(WasmBlockUpdate {
+            additions,
+            deletions,
+            proof,
+        })
+    }
+
+    fn added_hashes(&self) -> Vec<BitcoinNodeHash> {
+        self.additions
+            .iter()
+            .map(|addition| addition.hash)
+            .collect()
+    }
+}
+
+// Keeps a spend-ready proof per cached leaf, refreshed from each WasmUpdate
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ProofCache {
+    proofs: HashMap<BitcoinNodeHash, Proof<BitcoinNodeHash>>,
+}
+
+#[wasm_bindgen]
+impl ProofCache {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ProofCache {
+        ProofCache::default()
+    }
+
+    #[wasm_bindgen]
+    pub fn insert(&mut self, leaf_hash: &str, proof_json: &str) -> Result<(), UtreexoError> {
+        let leaf = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        let proof = parse_proof(proof_json)?;
+        if proof.targets.len() != 1 {
+            return Err(UtreexoError::from(format!(
+                "Cached proofs must prove a single leaf, got {} targets",
+                proof.targets.len()
+            )));
+        }
+
+        self.proofs.insert(leaf, proof);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get(&self, leaf_hash: &str) -> Result<Option<String>, UtreexoError> {
+        let leaf = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+
+        self.proofs
+            .get(&leaf)
+            .map(|proof| {
+                serde_json::to_string(proof)
+                    .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+            })
+            .transpose()
+    }
+
+    #[wasm_bindgen]
+    pub fn remove(&mut self, leaf_hash: &str) -> Result<bool, UtreexoError> {
+        let leaf = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        Ok(self.proofs.remove(&leaf).is_some())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.proofs.len()
+    }
+
+    // Brings every cached proof up to date with the update; leaves it deleted are dropped
+    #[wasm_bindgen]
+    pub fn apply(&mut self, update: &WasmUpdate) -> Result<(), UtreexoError> {
+        self.proofs.retain(|leaf, _| !update.deleted.contains(leaf));
+
+        for (leaf, proof) in self.proofs.iter_mut() {
+            let (updated, _) = update.refresh(proof, &[*leaf]).map_err(|e| {
+                UtreexoError::from(format!("Failed to update proof for leaf {}: {}", leaf, e))
+            })?;
+            *proof = updated;
+        }
+
+        Ok(())
+    }
+}
+
+// Blocks' proofs in order, shipped as one blob for catch-up sync (a "utreexo proof
+// file"). Each block carries its deletions, additions and the roots the accumulator
+// must have after it, so a replay can tell exactly which block is wrong.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ProofChain {
+    entries: Vec<ChainEntry>,
+}
+
+#[derive(Debug, Clone)]
+struct ChainEntry {
+    proof: Proof<BitcoinNodeHash>,
+    deletions: Vec<BitcoinNodeHash>,
+    additions: Vec<BitcoinNodeHash>,
+    // Biggest tree first, like WasmStump::roots
+    roots_after: Vec<BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl ProofChain {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ProofChain {
+        ProofChain::default()
+    }
+
+    // Appends the next block; deletions go in the proof's target order
+    #[wasm_bindgen]
+    pub fn push(
+        &mut self,
+        proof_json: &str,
+        del_hashes: Vec<JsValue>,
+        expected_roots_after: Vec<JsValue>,
+        add_hashes: Option<Vec<JsValue>>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let deletions = parse_hashes(del_hashes)?;
+        if deletions.len() != proof.targets.len() {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Block {} has {} deletions but its proof has {} targets",
+                    self.entries.len(),
+                    deletions.len(),
+                    proof.targets.len()
+                ),
+            ));
+        }
+
+        self.entries.push(ChainEntry {
+            proof,
+            deletions,
+            additions: parse_hashes(add_hashes.unwrap_or_default())?,
+            roots_after: parse_hashes(expected_roots_after)?,
+        });
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[wasm_bindgen]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode_proof_chain(&self.entries)
+    }
+
+    #[wasm_bindgen]
+    pub fn from_bytes(bytes: &[u8]) -> Result<ProofChain, UtreexoError> {
+        let entries = decode_proof_chain(&migrate_inner(bytes)?)?;
+        Ok(ProofChain { entries })
+    }
+
+    // Replays every block on a copy of `start` and returns the final stump. Fails at
+    // the first block whose proof doesn't apply or whose roots differ from the
+    // expected ones, naming the block; `start` itself is never touched.
+    #[wasm_bindgen]
+    pub fn verify_chain(&self, start: &WasmStump) -> Result<WasmStump, UtreexoError> {
+        let mut stump = start.inner.clone();
+        for (index, entry) in self.entries.iter().enumerate() {
+            modify_stump(&mut stump, &entry.proof, &entry.additions, &entry.deletions).map_err(
+                |e| UtreexoError::new(e.kind, format!("Block {}: {}", index, e.message)),
+            )?;
+            if stump.roots != entry.roots_after {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::RootsMismatch,
+                    format!(
+                        "Block {}: the roots after it aren't the expected ones",
+                        index
+                    ),
+                ));
+            }
+        }
+        Ok(WasmStump {
+            inner: stump,
+            sequence: start.sequence + self.entries.len() as u64,
+            _live: Live::stump(),
+        })
+    }
+}
+
+// Memoized parent hashes for WasmStump::verify_cached. Entries are tied to the stump
+// state they were computed against and dropped as soon as that stump changes.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct NodeCache {
+    state: Option<Stump>,
+    parents: HashMap<(BitcoinNodeHash, BitcoinNodeHash), BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl NodeCache {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> NodeCache {
+        NodeCache::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.parents.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.state = None;
+        self.parents.clear();
+    }
+}
+
+impl NodeCache {
+    fn invalidate_unless(&mut self, stump: &Stump) {
+        if self.state.as_ref() != Some(stump) {
+            self.parents.clear();
+            self.state = Some(stump.clone());
+        }
+    }
+
+    fn parent_hash(&mut self, left: &BitcoinNodeHash, right: &BitcoinNodeHash) -> BitcoinNodeHash {
+        *self
+            .parents
+            .entry((*left, *right))
+            .or_insert_with(|| BitcoinNodeHash::parent_hash(left, right))
+    }
+}
+
+// Verification results keyed by a digest of the stump state, the proof and the
+// proven hashes, so a proof arriving again (say from several peers) is answered
+// without hashing and a modified stump never hits an old entry. The least recently
+// used entry goes once `capacity` is reached. Wasm instances are single threaded, so
+// sharing one cache between callers needs no locking.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct VerificationCache {
+    capacity: usize,
+    // Result and the tick it was last used at
+    entries: HashMap<BitcoinNodeHash, (bool, u64)>,
+    by_use: BTreeMap<u64, BitcoinNodeHash>,
+    tick: u64,
+}
+
+#[wasm_bindgen]
+impl VerificationCache {
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> VerificationCache {
+        VerificationCache {
+            capacity,
+            ..VerificationCache::default()
+        }
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.by_use.clear();
+    }
+
+    // Same as stump.verify, answered from the cache when the same proof and hashes
+    // were already checked against the same stump state. Errors aren't cached.
+    #[wasm_bindgen]
+    pub fn verify_cached(
+        &mut self,
+        stump: &WasmStump,
+        proof_json: &str,
+        hashes: Vec<JsValue>,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let hashes = parse_hashes(hashes)?;
+
+        self.verify_inner(stump, &proof, &hashes)
+    }
+}
+
+impl VerificationCache {
+    fn verify_inner(
+        &mut self,
+        stump: &WasmStump,
+        proof: &Proof<BitcoinNodeHash>,
+        hashes: &[BitcoinNodeHash],
+    ) -> Result<bool, UtreexoError> {
+        let mut bytes = Vec::new();
+        let _ = stump.inner.serialize(&mut bytes);
+        bytes.extend(encode_wire_proof(proof));
+        for hash in hashes {
+            bytes.extend_from_slice(&**hash);
+        }
+        let key = digest(&bytes);
+
+        self.tick += 1;
+        if let Some((valid, used)) = self.entries.get_mut(&key) {
+            self.by_use.remove(used);
+            *used = self.tick;
+            self.by_use.insert(self.tick, key);
+            return Ok(*valid);
+        }
+
+        let valid = stump.verify_inner(proof, hashes)?;
+        if self.capacity == 0 {
+            return Ok(valid);
+        }
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.by_use.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(key, (valid, self.tick));
+        self.by_use.insert(self.tick, key);
+        Ok(valid)
+    }
+}
+
+// Verifies a proof whose deletion hashes arrive one at a time, e.g. from a network
+// reader. The stump's state is copied when it's created, so later modifies don't
+// affect it; hashes are pushed in the order of the proof's targets.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct StreamingVerifier {
+    stump: Stump,
+    proof: Proof<BitcoinNodeHash>,
+    hashes: Vec<BitcoinNodeHash>,
+}
+
+#[wasm_bindgen]
+impl StreamingVerifier {
+    // Proofs the stump would refuse outright fail here already, with the same errors
+    // as WasmStump::verify
+    #[wasm_bindgen(constructor)]
+    pub fn new(stump: &WasmStump, proof_json: &str) -> Result<StreamingVerifier, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        check_not_empty(stump.inner.leaves, &proof)?;
+        check_leaf_count(stump.inner.leaves, &proof)?;
+
+        Ok(StreamingVerifier {
+            stump: stump.inner.clone(),
+            hashes: Vec::with_capacity(proof.targets.len()),
+            proof,
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn push_hash(&mut self, hex: &str) -> Result<(), UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(hex).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        self.push_hash_inner(hash)
+    }
+
+    // Hashes still expected before finish
+    #[wasm_bindgen(getter)]
+    pub fn remaining(&self) -> usize {
+        self.proof.targets.len() - self.hashes.len()
+    }
+
+    // Fails with DeletionNotInProof while hashes are still missing
+    #[wasm_bindgen]
+    pub fn finish(&self) -> Result<bool, UtreexoError> {
+        if self.remaining() > 0 {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Got {} hashes but the proof has {} targets",
+                    self.hashes.len(),
+                    self.proof.targets.len()
+                ),
+            ));
+        }
+        Ok(self.stump.verify(&self.proof, &self.hashes) == Ok(true))
+    }
+}
+
+impl StreamingVerifier {
+    fn push_hash_inner(&mut self, hash: BitcoinNodeHash) -> Result<(), UtreexoError> {
+        if self.remaining() == 0 {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "The proof only has {} targets, got another hash",
+                    self.proof.targets.len()
+                ),
+            ));
+        }
+        self.hashes.push(hash);
+        Ok(())
+    }
+}
+
+// Pollard wrapper for WASM (full accumulator)
+#[wasm_bindgen]
+pub struct WasmPollard {
+    inner: Pollard<BitcoinNodeHash>,
+    roots_changed: Option<js_sys::Function>,
+    // Caller data for remembered leaves, dropped once a leaf is deleted
+    leaf_data: HashMap<BitcoinNodeHash, Vec<u8>>,
+    // Every leaf added or restored, until deleted or pruned, as upstream keeps it in its
+    // leaf map (which can't be listed). The stored nodes whose hash is in here are the
+    // provable leaves, forgotten siblings included; compact and prune trim it to those.
+    remembered: HashSet<BitcoinNodeHash>,
+    // serialize_chunk's state between the chunks of one export
+    export: RefCell<Option<ChunkExport>>,
+    _live: Live,
+}
+
+// The part of the encoding serialize_chunk can't get from rustreexo, worked out when
+// an export starts and kept for the chunks that follow
+struct ChunkExport {
+    next_cursor: u64,
+    // Length of the envelope plus rustreexo's encoding
+    head_len: u64,
+    positions: Vec<u64>,
+}
+
+impl Default for WasmPollard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl WasmPollard {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmPollard {
+        WasmPollard {
+            inner: Pollard::new(),
+            roots_changed: None,
+            leaf_data: HashMap::new(),
+            remembered: HashSet::new(),
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn from_roots(roots: Vec<JsValue>, leaves: u64) -> Result<WasmPollard, UtreexoError> {
+        let root_hashes: Result<Vec<BitcoinNodeHash>, UtreexoError> = roots
+            .into_iter()
+            .map(|js_val| {
+                let hex_str = js_val.as_string().ok_or_else(|| {
+                    UtreexoError::new(UtreexoErrorKind::InvalidHash, "Root hash must be a string")
+                })?;
+                BitcoinNodeHash::from_str(&hex_str).map_err(|e| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::InvalidHash,
+                        format!("Invalid root hash: {}", e),
+                    )
+                })
+            })
+            .collect();
+
+        let root_hashes = root_hashes?;
+        check_leaves(leaves)?;
+        let pollard = Pollard::from_roots(root_hashes, leaves);
+        Ok(WasmPollard {
+            inner: pollard,
+            roots_changed: None,
+            leaf_data: HashMap::new(),
+            remembered: HashSet::new(),
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        })
+    }
+
+    // A pollard holding only the stump's roots, remembering nothing yet. Unlike
+    // from_roots it takes the stump's biggest-first roots as they are, and leaves
+    // added from then on can be remembered as usual.
+    #[wasm_bindgen]
+    pub fn from_stump(stump: &WasmStump) -> Result<WasmPollard, UtreexoError> {
+        let stump = checked_stump(stump.inner.leaves, stump.inner.roots.clone())?;
+        let roots = stump.roots.iter().rev().copied().collect();
+        let pollard = rebuild_pollard(stump.leaves, roots, Vec::new()).map_err(|e| {
+            UtreexoError::from(format!("Failed to build pollard from stump: {}", e))
+        })?;
+        Ok(WasmPollard {
+            inner: pollard,
+            roots_changed: None,
+            leaf_data: HashMap::new(),
+            remembered: HashSet::new(),
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn num_leaves(&self) -> u64 {
+        self.inner.leaves()
+    }
+
+    // Only accepts the built-in empty hash (32 zero bytes), see check_empty_hash
+    #[wasm_bindgen]
+    pub fn configure_empty_hash(&mut self, hex: &str) -> Result<(), UtreexoError> {
+        check_empty_hash(hex)
+    }
+
+    // Checks the invariants untrusted state could break: the root count fits the leaf
+    // count and every stored pair of nodes hashes to the stored parent above it, all
+    // the way up to the roots. Fails with InconsistentRoots or CorruptState.
+    #[wasm_bindgen]
+    pub fn self_check(&self) -> Result<(), UtreexoError> {
+        self.self_check_inner()
+    }
+
+    // Exactly serialize().length, counted without building the buffer. Leaf data
+    // isn't part of it.
+    #[wasm_bindgen]
+    pub fn serialized_size(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        // Counting never fails the write
+        let _ = write_pollard(self, &mut counter);
+        counter.0
+    }
+
+    // Independent copy with the same remembered leaves and leaf data. Pollard's own
+    // Clone shares nodes, so it goes through the encoding. The roots_changed callback
+    // stays with the original.
+    #[wasm_bindgen(js_name = clone)]
+    pub fn clone_js(&self) -> Result<WasmPollard, UtreexoError> {
+        let mut copy = decode_pollard(&encode_pollard(self)?)?;
+        copy.leaf_data = self.leaf_data.clone();
+        Ok(copy)
+    }
+
+    // The whole binary encoding in one buffer, remembered leaves included; once
+    // deserialized they can still be proven
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, UtreexoError> {
+        encode_pollard(self)
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(bytes: &[u8]) -> Result<WasmPollard, UtreexoError> {
+        decode_pollard(&migrate_inner(bytes)?)
+    }
+
+    // Up to `max_bytes` of the pollard's binary encoding, starting at byte `cursor`, as
+    // {bytes, next_cursor, done}; PollardLoader puts the chunks back together. The
+    // remembered positions are listed once when an export starts at cursor 0 and
+    // served from there. rustreexo can only encode its part in one go, so each chunk
+    // of it re-runs that up to the chunk, keeping nothing before it. Modifying the
+    // pollard in between mixes states.
+    #[wasm_bindgen]
+    pub fn serialize_chunk(&self, cursor: u64, max_bytes: usize) -> Result<JsValue, UtreexoError> {
+        let (bytes, done) = self
+            .serialize_chunk_inner(cursor, max_bytes)
+            .map_err(|e| UtreexoError::from(format!("Failed to encode pollard: {}", e)))?;
+        let next_cursor = cursor + bytes.len() as u64;
+
+        let chunk = js_sys::Object::new();
+        // Setting plain properties on a fresh object can't fail
+        let _ = js_sys::Reflect::set(&chunk, &"bytes".into(), &Uint8Array::from(&bytes[..]));
+        let _ = js_sys::Reflect::set(&chunk, &"next_cursor".into(), &JsValue::from(next_cursor));
+        let _ = js_sys::Reflect::set(&chunk, &"done".into(), &JsValue::from(done));
+        Ok(chunk.into())
+    }
+
+    // Smallest tree first, the reverse of WasmStump::roots (see roots_biggest_first)
+    #[wasm_bindgen]
+    pub fn roots(&self) -> Vec<JsValue> {
+        self.inner
+            .roots()
+            .iter()
+            .map(|root| {
+                let hash = Hash { inner: *root };
+                JsValue::from(hash.to_hex())
+            })
+            .collect()
+    }
+
+    // Same order as `roots`, see root_words
+    #[wasm_bindgen]
+    pub fn roots_u32(&self) -> Vec<u32> {
+        root_words(&self.inner.roots())
+    }
+
+    // The `_raw` variants take and return hashes as one flat buffer of 32 bytes per
+    // hash, as on WasmStump; remember flags are packed as for add_with_flags
+
+    #[wasm_bindgen]
+    pub fn roots_raw(&self) -> Vec<u8> {
+        flat_hashes(&self.inner.roots())
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_raw(&self, proof_json: &str, hashes: &[u8]) -> Result<bool, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_flat_hashes(hashes)?;
+        self.verify_inner(&proof, &del_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn batch_proof_raw(
+        &self,
+        target_hashes: &[u8],
+        max_millis: Option<f64>,
+    ) -> Result<String, UtreexoError> {
+        self.prove_hashes(&parse_flat_hashes(target_hashes)?, max_millis)?
+            .to_json()
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_raw(
+        &mut self,
+        proof_json: &str,
+        add_hashes: &[u8],
+        remember_flags: &[u8],
+        del_hashes: &[u8],
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_hashes = parse_flat_hashes(add_hashes)?;
+        let flags = unpack_flags(remember_flags, add_hashes.len())?;
+        let del_hashes = parse_flat_hashes(del_hashes)?;
+
+        let additions: Vec<_> = add_hashes
+            .into_iter()
+            .zip(flags)
+            .map(|(hash, remember)| PollardAddition { hash, remember })
+            .collect();
+        self.modify_checked(proof, &additions, &del_hashes)
+    }
+
+    // The roots in WasmStump's order, for comparing against stumps and other
+    // implementations without reversing by hand
+    #[wasm_bindgen]
+    pub fn roots_biggest_first(&self) -> Vec<JsValue> {
+        self.inner
+            .roots()
+            .iter()
+            .rev()
+            .map(|root| JsValue::from(hex(root)))
+            .collect()
+    }
+
+    // Merged from checked single proofs; with `max_millis`, gives up with Timeout
+    // once the budget is spent between two of them
+    #[wasm_bindgen]
+    pub fn batch_proof(
+        &self,
+        target_hashes: Vec<JsValue>,
+        max_millis: Option<f64>,
+    ) -> Result<String, UtreexoError> {
+        self.prove(target_hashes, max_millis)?.to_json()
+    }
+
+    // Same as batch_proof, returning the proof as a WasmProof instead of JSON
+    #[wasm_bindgen]
+    pub fn prove(
+        &self,
+        target_hashes: Vec<JsValue>,
+        max_millis: Option<f64>,
+    ) -> Result<WasmProof, UtreexoError> {
+        self.prove_hashes(&parse_hashes(target_hashes)?, max_millis)
+    }
+
+    // Same as batch_proof, returning {json, wire} with the proof in both its JSON and
+    // rustreexo wire encodings (as verify_wire takes it), generated once
+    #[wasm_bindgen]
+    pub fn batch_proof_encoded(
+        &self,
+        target_hashes: Vec<JsValue>,
+    ) -> Result<JsValue, UtreexoError> {
+        let hashes = parse_hashes(target_hashes)?;
+        let proof = self.prove_hashes(&hashes, None)?.inner;
+        let json = serde_json::to_string(&proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))?;
+        let wire = encode_wire_proof(&proof);
+
+        let encoded = js_sys::Object::new();
+        // Setting plain properties on a fresh object can't fail
+        let _ = js_sys::Reflect::set(&encoded, &"json".into(), &JsValue::from(json));
+        let _ = js_sys::Reflect::set(&encoded, &"wire".into(), &Uint8Array::from(&wire[..]));
+        Ok(encoded.into())
+    }
+
+    // Proof JSON plus how many of its wire bytes each target accounts for, in the order
+    // of `target_hashes`; see proof_byte_shares for how shared hashes are split
+    #[wasm_bindgen]
+    pub fn batch_proof_with_sizes(
+        &self,
+        target_hashes: Vec<JsValue>,
+    ) -> Result<JsValue, UtreexoError> {
+        let hashes = parse_hashes(target_hashes)?;
+        let proof = self.prove_hashes(&hashes, None)?.inner;
+        let json = serde_json::to_string(&proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))?;
+        let shares = proof_byte_shares(&proof, self.inner.leaves());
+
+        let sized = js_sys::Object::new();
+        // Setting plain properties on a fresh object can't fail
+        let _ = js_sys::Reflect::set(&sized, &"proof".into(), &JsValue::from(json));
+        let _ = js_sys::Reflect::set(
+            &sized,
+            &"per_target_bytes".into(),
+            &Float64Array::from(&shares[..]),
+        );
+        Ok(sized.into())
+    }
+
+    #[wasm_bindgen]
+    pub fn prove_single(&self, leaf_hash: &str) -> Result<String, UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+
+        let proof = self
+            .inner
+            .prove_single(hash)
+            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+
+        serde_json::to_string(&proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+    }
+
+    // Same as prove_single, for a leaf given as its 32 raw bytes; the proof comes back
+    // in the versioned binary form (as proof_to_bytes writes it)
+    #[wasm_bindgen]
+    pub fn prove_single_bytes(&self, leaf_hash: &[u8]) -> Result<Vec<u8>, UtreexoError> {
+        let proof = self
+            .inner
+            .prove_single(hash_from_slice(leaf_hash)?)
+            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+        Ok(encode_proof(&proof))
+    }
+
+    // Always fails with Unsupported: leaves sit in insertion order, not sorted by hash,
+    // so there are no neighbours whose inclusion would bound where a missing leaf
+    // would have to be, and nothing short of every leaf shows one isn't there
+    #[wasm_bindgen]
+    pub fn prove_absence(&self, leaf_hash: &str) -> Result<String, UtreexoError> {
+        BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        Err(UtreexoError::new(
+            UtreexoErrorKind::Unsupported,
+            "Utreexo can't prove absence: leaves are ordered by insertion, not by hash, so no \
+             neighbouring leaves bound where a missing one would be",
+        ))
+    }
+
+    // Same as batch_proof, keeping the proven hashes alongside the proof
+    #[wasm_bindgen]
+    pub fn prove_bundle(
+        &self,
+        target_hashes: Vec<JsValue>,
+    ) -> Result<WasmProofBundle, UtreexoError> {
+        let hashes = parse_hashes(target_hashes)?;
+        let proof = self.prove_hashes(&hashes, None)?.inner;
+
+        let mut bundle = WasmProofBundle::from_parts(proof, hashes)?;
+        let roots: Vec<BitcoinNodeHash> = self.inner.roots().into_iter().rev().collect();
+        bundle.valid_at = Some((self.inner.leaves(), state_hash(self.inner.leaves(), &roots)));
+        Ok(bundle)
+    }
+
+    // One independent single-leaf proof JSON per input leaf, in input order
+    #[wasm_bindgen]
+    pub fn prove_each(&self, leaf_hashes: Vec<JsValue>) -> Result<Vec<JsValue>, UtreexoError> {
+        let leaf_hashes = parse_hashes(leaf_hashes)?;
+
+        leaf_hashes
+            .into_iter()
+            .map(|hash| {
+                let proof = self.inner.prove_single(hash).map_err(|e| {
+                    UtreexoError::from(format!("Failed to generate proof for leaf {}: {}", hash, e))
+                })?;
+
+                serde_json::to_string(&proof)
+                    .map(JsValue::from)
+                    .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+            })
+            .collect()
+    }
+
+    // One batch proof for all the leaves, fetching every shared proof hash once, plus
+    // {proof, positions} where positions[i] is the target proving leaf_hashes[i]
+    #[wasm_bindgen]
+    pub fn prove_many(&self, leaf_hashes: Vec<JsValue>) -> Result<JsValue, UtreexoError> {
+        let hashes = parse_hashes(leaf_hashes)?;
+        let proof = self.prove_hashes(&hashes, None)?;
+        // batch_proof keeps the targets in the order of the hashes it was given
+        let positions: Vec<f64> = proof.inner.targets.iter().map(|&pos| pos as f64).collect();
+
+        let proven = js_sys::Object::new();
+        // Setting plain properties on a fresh object can't fail
+        let _ = js_sys::Reflect::set(&proven, &"proof".into(), &JsValue::from(proof.to_json()?));
+        let _ = js_sys::Reflect::set(
+            &proven,
+            &"positions".into(),
+            &Float64Array::from(&positions[..]),
+        );
+        Ok(proven.into())
+    }
+
+    #[wasm_bindgen]
+    pub fn verify(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<bool, UtreexoError> {
+        self.verify_proof(&WasmProof::from_json(proof_json)?, hashes)
+    }
+
+    // Same as verify, without parsing the proof again
+    #[wasm_bindgen]
+    pub fn verify_proof(
+        &self,
+        proof: &WasmProof,
+        hashes: Vec<JsValue>,
+    ) -> Result<bool, UtreexoError> {
+        let del_hashes = parse_hashes(hashes)?;
+        self.verify_inner(&proof.inner, &del_hashes)
+    }
+
+    // Same as verify, throwing with the reason instead of returning false, see
+    // WasmStump::check_proof
+    #[wasm_bindgen]
+    pub fn check_proof(&self, proof_json: &str, hashes: Vec<JsValue>) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+        check_not_empty(self.inner.leaves(), &proof)?;
+        check_leaf_count(self.inner.leaves(), &proof)?;
+        explain_verify(self.inner.verify(&proof, &del_hashes))
+    }
+
+    // With `reject_duplicates`, additions that repeat a provable leaf (or each other)
+    // fail with DuplicateLeaf; off by default since it proves every addition
+    #[wasm_bindgen]
+    pub fn modify(
+        &mut self,
+        proof_json: &str,
+        additions_json: &str,
+        del_hashes: Vec<JsValue>,
+        reject_duplicates: Option<bool>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_items = parse_additions(additions_json)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        if reject_duplicates.unwrap_or(false) {
+            self.check_novel(&add_items, &del_hashes)?;
+        }
+        self.modify_checked(proof, &add_items, &del_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn verify_bundle(&self, bundle: &WasmProofBundle) -> Result<bool, UtreexoError> {
+        self.verify_inner(&bundle.proof, &bundle.leaves)
+    }
+
+    #[wasm_bindgen]
+    pub fn apply_block(&mut self, update: &WasmBlockUpdate) -> Result<(), UtreexoError> {
+        self.modify_checked(update.proof.clone(), &update.additions, &update.deletions)
+    }
+
+    // Same as modify, returning the position each deleted leaf had, in the same order
+    // as `del_hashes`
+    #[wasm_bindgen]
+    pub fn modify_with_deletions(
+        &mut self,
+        proof_json: &str,
+        additions_json: &str,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<Vec<u64>, UtreexoError> {
+        let proof = parse_proof(proof_json)?;
+        let add_items = parse_additions(additions_json)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        // modify_checked refuses targets that don't pair up with the hashes one to one
+        let positions = proof.targets.clone();
+        self.modify_checked(proof, &add_items, &del_hashes)?;
+        Ok(positions)
+    }
+
+    // Deletes the bundle's leaves and applies the additions
+    #[wasm_bindgen]
+    pub fn modify_bundle(
+        &mut self,
+        bundle: &WasmProofBundle,
+        additions_json: &str,
+    ) -> Result<(), UtreexoError> {
+        let add_items = parse_additions(additions_json)?;
+        self.modify_checked(bundle.proof.clone(), &add_items, &bundle.leaves)
+    }
+
+    // Same as verify and modify, with the proof JSON as UTF-8 bytes
+    #[wasm_bindgen]
+    pub fn verify_bytes(
+        &self,
+        proof_json: &[u8],
+        hashes: Vec<JsValue>,
+    ) -> Result<bool, UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let del_hashes = parse_hashes(hashes)?;
+        self.verify_inner(&proof, &del_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_bytes(
+        &mut self,
+        proof_json: &[u8],
+        additions_json: &str,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let proof = parse_proof_bytes(proof_json)?;
+        let add_items = parse_additions(additions_json)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_checked(proof, &add_items, &del_hashes)
+    }
+
+    // Hashes from a remembered leaf up to its root, with each node's sibling and
+    // whether the node is the left child
+    #[wasm_bindgen]
+    pub fn merkle_path(&self, leaf_hash: &str) -> Result<JsValue, UtreexoError> {
+        let leaf = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        to_js(&self.merkle_path_inner(leaf)?)
+    }
+
+    // Adds leaves with their remember flags packed one bit each, see pack_remember_flags
+    #[wasm_bindgen]
+    pub fn add_with_flags(
+        &mut self,
+        hashes: Vec<JsValue>,
+        flags: &[u8],
+    ) -> Result<(), UtreexoError> {
+        let hashes = parse_hashes(hashes)?;
+        let flags = unpack_flags(flags, hashes.len())?;
+        let additions: Vec<_> = hashes
+            .into_iter()
+            .zip(flags)
+            .map(|(hash, remember)| PollardAddition { hash, remember })
+            .collect();
+        self.modify_checked(Proof::default(), &additions, &[])
+    }
+
+    // Adds a single remembered leaf and returns its proof right away
+    #[wasm_bindgen]
+    pub fn add_and_prove(&mut self, leaf_hash: &str) -> Result<String, UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        self.modify_checked(
+            Proof::default(),
+            &[PollardAddition {
+                hash,
+                remember: true,
+            }],
+            &[],
+        )?;
+
+        let proof = self
+            .inner
+            .prove_single(hash)
+            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+        serde_json::to_string(&proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+    }
+
+    // Deletes remembered leaves, building the proof for them from this pollard.
+    // Returns the position each leaf had, in the same order as `leaf_hashes`.
+    #[wasm_bindgen]
+    pub fn delete(&mut self, leaf_hashes: Vec<JsValue>) -> Result<Vec<u64>, UtreexoError> {
+        let del_hashes = parse_hashes(leaf_hashes)?;
+        self.delete_inner(&del_hashes)
+    }
+
+    // The leaves among `del_hashes` this pollard can't prove itself, in the same order:
+    // the ones delete refuses and a modify_with_supplement proof has to cover
+    #[wasm_bindgen]
+    pub fn missing_proof_targets(
+        &self,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let del_hashes = parse_hashes(del_hashes)?;
+        Ok(self
+            .missing_proof_targets_inner(&del_hashes)
+            .into_iter()
+            .map(|hash| JsValue::from(Hash { inner: hash }.to_hex()))
+            .collect())
+    }
+
+    // Adds a single leaf carrying `data`, which leaf_data returns until the leaf is
+    // deleted or pruned. Forgotten leaves get pruned, so their data isn't kept at all.
+    #[wasm_bindgen]
+    pub fn add_leaf_with_data(
+        &mut self,
+        leaf_hash: &str,
+        remember: bool,
+        data: &[u8],
+    ) -> Result<(), UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        self.modify_checked(Proof::default(), &[PollardAddition { hash, remember }], &[])?;
+        if remember {
+            self.leaf_data.insert(hash, data.to_vec());
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn leaf_data(&self, leaf_hash: &str) -> Result<Option<Vec<u8>>, UtreexoError> {
+        let hash = BitcoinNodeHash::from_str(leaf_hash).map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidHash,
+                format!("Invalid hash: {}", e),
+            )
+        })?;
+        Ok(self.leaf_data.get(&hash).cloned())
+    }
+
+    // Up to `limit` remembered leaf hashes starting at `offset`, ordered by position so
+    // pages line up as long as the pollard isn't modified in between
+    #[wasm_bindgen]
+    pub fn remembered_leaves_page(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<JsValue>, UtreexoError> {
+        let remembered = self
+            .first_remembered(offset.saturating_add(limit))
+            .map_err(|e| UtreexoError::from(format!("Failed to list remembered leaves: {}", e)))?;
+        Ok(remembered
+            .into_iter()
+            .skip(offset)
+            .map(|(_, hash)| JsValue::from(hex(&hash)))
+            .collect())
+    }
+
+    #[wasm_bindgen]
+    pub fn remembered_count(&self) -> Result<usize, UtreexoError> {
+        let mut count = 0;
+        self.walk_nodes(|_, hash| {
+            if self.remembered.contains(&hash) {
+                count += 1;
+            }
+        })
+        .map_err(|e| UtreexoError::from(format!("Failed to list remembered leaves: {}", e)))?;
+        Ok(count)
+    }
+
+    // Every node the pollard holds in memory: roots, interior nodes and leaves
+    #[wasm_bindgen]
+    pub fn num_nodes(&self) -> Result<u64, UtreexoError> {
+        Ok(self.nodes_inner()?.len() as u64)
+    }
+
+    // Rough heap footprint of the nodes, the leaf index and the leaf data. Node sizes
+    // follow the upstream layout for the running target, allocator overhead is left out.
+    #[wasm_bindgen]
+    pub fn memory_bytes(&self) -> Result<u64, UtreexoError> {
+        // Pollard nodes live in an Rc: strong and weak counts, a remember flag, the
+        // hash, then RefCells holding the aunt and both nieces
+        let node_bytes = 2 * std::mem::size_of::<usize>()
+            + std::mem::size_of::<(
+                bool,
+                BitcoinNodeHash,
+                [std::cell::RefCell<Option<std::rc::Rc<()>>>; 3],
+            )>();
+        // The leaf map keys each leaf node by hash, plus a control byte per entry, and
+        // the remembered set holds a hash and a control byte per leaf
+        let map_entry_bytes = std::mem::size_of::<(BitcoinNodeHash, std::rc::Weak<()>)>() + 1;
+        let remembered_bytes = self.remembered.len() * (std::mem::size_of::<BitcoinNodeHash>() + 1);
+
+        let leaves = self.inner.leaves();
+        let nodes = self.nodes_inner()?;
+        let leaf_nodes = nodes.iter().filter(|(pos, _)| *pos < leaves).count();
+        let data_bytes: usize = self
+            .leaf_data
+            .values()
+            .map(|data| std::mem::size_of::<(BitcoinNodeHash, Vec<u8>)>() + data.len())
+            .sum();
+
+        Ok((std::mem::size_of::<Self>()
+            + nodes.len() * node_bytes
+            + leaf_nodes * map_entry_bytes
+            + remembered_bytes
+            + data_bytes) as u64)
+    }
+
+    // Roots, remembered leaves with their proofs and leaf data as plain JSON, for
+    // rebuilding the pollard in a worker with from_transferable_json. The roots
+    // changed callback stays behind.
+    #[wasm_bindgen]
+    pub fn to_transferable_json(&self) -> Result<String, UtreexoError> {
+        let leaves = self.inner.leaves();
+        let remembered = self
+            .remembered_inner()
+            .map_err(|e| UtreexoError::from(format!("Failed to list remembered leaves: {}", e)))?
+            .into_iter()
+            .map(|(position, hash, proof)| {
+                let needed = proof_positions(&proof.targets, leaves).len();
+                TransferableLeaf {
+                    position,
+                    hash: hash.to_string(),
+                    proof: proof.hashes[..needed]
+                        .iter()
+                        .map(transferable_hex)
+                        .collect(),
+                    data: self.leaf_data.get(&hash).map(|data| bytes_hex(data)),
+                }
+            })
+            .collect();
+
+        let record = TransferableRecord::Pollard {
+            leaves,
+            roots: self.inner.roots().iter().map(transferable_hex).collect(),
+            remembered,
+        };
+        serde_json::to_string(&record)
+            .map_err(|e| UtreexoError::from(format!("Failed to serialize to JSON: {}", e)))
+    }
+
+    // Every remembered leaf's proof is checked against the roots before rebuilding
+    #[wasm_bindgen]
+    pub fn from_transferable_json(json_str: &str) -> Result<WasmPollard, UtreexoError> {
+        let (leaves, roots, leaf_records) = match parse_transferable(json_str)? {
+            TransferableRecord::Pollard {
+                leaves,
+                roots,
+                remembered,
+            } => (leaves, roots, remembered),
+            TransferableRecord::Stump { .. } => {
+                return Err(UtreexoError::from(
+                    "Expected a transferable pollard but got a stump",
+                ))
+            }
+        };
+
+        let roots = parse_transferable_hashes(&roots)?;
+        let stump = checked_stump(leaves, roots.iter().rev().copied().collect())?;
+
+        let mut entries = Vec::with_capacity(leaf_records.len());
+        let mut leaf_data = HashMap::new();
+        for leaf in leaf_records {
+            let hash = BitcoinNodeHash::from_str(&leaf.hash).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    format!("Invalid hash: {}", e),
+                )
+            })?;
+            let proof = Proof::new(vec![leaf.position], parse_transferable_hashes(&leaf.proof)?);
+            if let Some(data) = &leaf.data {
+                leaf_data.insert(hash, parse_bytes_hex(data)?);
+            }
+            entries.push((hash, proof));
+        }
+        let remembered = checked_remembered(&stump, entries)?;
+
+        let hashes = remembered.iter().map(|(_, hash, _)| *hash).collect();
+        let pollard = rebuild_pollard(leaves, roots, remembered)
+            .map_err(|e| UtreexoError::from(format!("Failed to rebuild pollard: {}", e)))?;
+        Ok(WasmPollard {
+            inner: pollard,
+            roots_changed: None,
+            leaf_data,
+            remembered: hashes,
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        })
+    }
+
+    // Registers a callback invoked with the new roots after every successful modify;
+    // registering again replaces the previous one
+    #[wasm_bindgen]
+    pub fn on_roots_changed(&mut self, cb: js_sys::Function) {
+        self.roots_changed = Some(cb);
+    }
+
+    // Rebuilds the node storage into fresh, tightly sized allocations. Roots and every
+    // proof that verifies stay exactly the same; fails without changes when the nodes
+    // can't be read back.
+    #[wasm_bindgen]
+    pub fn compact(&mut self) -> Result<(), UtreexoError> {
+        self.compact_inner()
+    }
+
+    // Forgets the given remembered leaves along with the nodes only their paths needed,
+    // dropping their leaf data. Roots and the proofs of every other remembered leaf stay
+    // the same; fails without changes when any of them isn't remembered.
+    #[wasm_bindgen]
+    pub fn prune(&mut self, leaf_hashes: Vec<JsValue>) -> Result<(), UtreexoError> {
+        let leaf_hashes = parse_hashes(leaf_hashes)?;
+        self.prune_inner(&leaf_hashes)
+    }
+
+    // Persists the roots plus just what's needed to prove `my_leaves`, which all have
+    // to be remembered; import_wallet_state restores a pollard remembering only them
+    #[wasm_bindgen]
+    pub fn export_wallet_state(&self, my_leaves: Vec<JsValue>) -> Result<Vec<u8>, UtreexoError> {
+        let my_leaves = parse_hashes(my_leaves)?;
+        self.export_wallet_state_inner(&my_leaves)
+    }
+
+    #[wasm_bindgen]
+    pub fn import_wallet_state(bytes: &[u8]) -> Result<WasmPollard, UtreexoError> {
+        let (stump, entries) = decode_wallet(&migrate_inner(bytes)?)?;
+        let remembered = checked_remembered(&stump, entries)?;
+
+        let hashes = remembered.iter().map(|(_, hash, _)| *hash).collect();
+        let roots = stump.roots.iter().rev().copied().collect();
+        let pollard = rebuild_pollard(stump.leaves, roots, remembered)
+            .map_err(|e| UtreexoError::from(format!("Failed to import wallet state: {}", e)))?;
+        Ok(WasmPollard {
+            inner: pollard,
+            roots_changed: None,
+            leaf_data: HashMap::new(),
+            remembered: hashes,
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        })
+    }
+
+    // Like modify, but the proof only has to cover deletions whose paths this pollard
+    // has forgotten; cached deletions are proven from the pollard itself
+    #[wasm_bindgen]
+    pub fn modify_with_supplement(
+        &mut self,
+        supplement_json: &str,
+        additions_json: &str,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let supplement = parse_proof(supplement_json)?;
+        let add_items = parse_additions(additions_json)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_with_supplement_inner(supplement, &add_items, &del_hashes)
+    }
+
+    #[wasm_bindgen]
+    pub fn modify_with_supplement_bytes(
+        &mut self,
+        supplement_json: &[u8],
+        additions_json: &str,
+        del_hashes: Vec<JsValue>,
+    ) -> Result<(), UtreexoError> {
+        let supplement = parse_proof_bytes(supplement_json)?;
+        let add_items = parse_additions(additions_json)?;
+        let del_hashes = parse_hashes(del_hashes)?;
+
+        self.modify_with_supplement_inner(supplement, &add_items, &del_hashes)
+    }
+}
+
+// Decodes serialize_chunk's chunks as they're pushed, in order, and rebuilds the
+// pollard from them
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct PollardLoader {
+    decoder: PollardDecoder,
+    // The first chunk that failed to decode fails every call after it too
+    error: Option<UtreexoError>,
+}
+
+#[wasm_bindgen]
+impl PollardLoader {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PollardLoader {
+        PollardLoader::default()
+    }
+
+    // Fails with CorruptState as soon as the bytes so far can't start a pollard
+    #[wasm_bindgen]
+    pub fn push_chunk(&mut self, chunk: &[u8]) -> Result<(), UtreexoError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.decoder.push(chunk).map_err(|error| {
+            self.error = Some(error.clone());
+            error
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn bytes_received(&self) -> u64 {
+        self.decoder.received
+    }
+
+    // Fails unless the chunks add up to exactly one encoded pollard. The same leaves
+    // are remembered, but leaf data and the roots changed callback aren't carried over.
+    #[wasm_bindgen]
+    pub fn finish(&self) -> Result<WasmPollard, UtreexoError> {
+        if let Some(error) = &self.error {
+            return Err(error.clone());
+        }
+        self.decoder.finish().map(WasmPollard::restored)
+    }
+}
+
+impl WasmPollard {
+    // A decoded pollard remembering `remembered`, with no leaf data or callback yet
+    fn restored(
+        (inner, remembered): (Pollard<BitcoinNodeHash>, HashSet<BitcoinNodeHash>),
+    ) -> WasmPollard {
+        WasmPollard {
+            inner,
+            roots_changed: None,
+            leaf_data: HashMap::new(),
+            remembered,
+            export: RefCell::new(None),
+            _live: Live::pollard(),
+        }
+    }
+
+    fn serialize_chunk_inner(
+        &self,
+        cursor: u64,
+        max_bytes: usize,
+    ) -> Result<(Vec<u8>, bool), String> {
+        let mut export = self.export.borrow_mut();
+        let state = match export.take() {
+            Some(state) if cursor != 0 && state.next_cursor == cursor => state,
+            _ => {
+                let mut positions = Vec::new();
+                let len = self.walk_nodes(|pos, hash| {
+                    if self.remembered.contains(&hash) {
+                        positions.push(pos);
+                    }
+                })?;
+                positions.sort_unstable();
+                ChunkExport {
+                    next_cursor: cursor,
+                    head_len: 2 + len as u64,
+                    positions,
+                }
+            }
+        };
+
+        let mut bytes = Vec::new();
+        if cursor < state.head_len {
+            let mut window = ByteWindow {
+                skip: cursor,
+                max: max_bytes,
+                bytes: Vec::new(),
+            };
+            // The window fails the write once it's full, which ends the encoding early
+            let _ = window
+                .write_all(&[FORMAT_VERSION, TAG_POLLARD])
+                .and_then(|()| self.inner.serialize(&mut window));
+            bytes = window.bytes;
+        }
+
+        // Past rustreexo's part: the count, then the positions, 8 bytes each
+        let tail_len = 8 * (state.positions.len() as u64 + 1);
+        let mut offset = (cursor + bytes.len() as u64).saturating_sub(state.head_len);
+        while bytes.len() < max_bytes
+            && cursor + bytes.len() as u64 >= state.head_len
+            && offset < tail_len
+        {
+            let word = match offset / 8 {
+                0 => state.positions.len() as u64,
+                index => state.positions[index as usize - 1],
+            };
+            let from = (offset % 8) as usize;
+            let take = (8 - from).min(max_bytes - bytes.len());
+            bytes.extend_from_slice(&word.to_le_bytes()[from..from + take]);
+            offset += take as u64;
+        }
+
+        let next_cursor = cursor + bytes.len() as u64;
+        let done = next_cursor == state.head_len + tail_len;
+        if !done {
+            *export = Some(ChunkExport {
+                next_cursor,
+                ..state
+            });
+        }
+        Ok((bytes, done))
+    }
+
+    fn batch_proof_within(
+        &self,
+        hashes: &[BitcoinNodeHash],
+        expired: &mut impl FnMut() -> bool,
+    ) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+        let mut proofs = Vec::with_capacity(hashes.len());
+        for (proven, hash) in hashes.iter().enumerate() {
+            if expired() {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::Timeout,
+                    format!(
+                        "Proof generation ran out of time after {} of {} leaves",
+                        proven,
+                        hashes.len()
+                    ),
+                ));
+            }
+            let proof = self.inner.prove_single(*hash).map_err(|e| {
+                UtreexoError::from(format!("Failed to generate proof for leaf {}: {}", hash, e))
+            })?;
+            if self.inner.verify(&proof, &[*hash]) != Ok(true) {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::ProofVerificationFailed,
+                    format!("Proof for leaf {} does not verify", hash),
+                ));
+            }
+            proofs.push(proof);
+        }
+        merge_proofs_inner(&proofs, self.inner.leaves())
+    }
+
+    // Only leaves the pollard can prove are caught, forgotten ones slip through; a
+    // leaf deleted in the same batch may be added back
+    fn check_novel(
+        &self,
+        add_items: &[PollardAddition<BitcoinNodeHash>],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<(), UtreexoError> {
+        let mut seen = HashSet::new();
+        for addition in add_items {
+            let hash = addition.hash;
+            let remembered = !del_hashes.contains(&hash)
+                && self.inner.prove_single(hash).map_or(false, |proof| {
+                    self.inner.verify(&proof, &[hash]) == Ok(true)
+                });
+            if remembered || !seen.insert(hash) {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::DuplicateLeaf,
+                    format!("Leaf {} is already in the accumulator", hash),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn prove_hashes(
+        &self,
+        hashes: &[BitcoinNodeHash],
+        max_millis: Option<f64>,
+    ) -> Result<WasmProof, UtreexoError> {
+        // Batch proofs from a churned pollard don't always verify, so every proof is
+        // merged from checked single proofs, with or without a time budget
+        let proof = match max_millis {
+            Some(max_millis) => {
+                let start = js_sys::Date::now();
+                self.batch_proof_within(hashes, &mut || js_sys::Date::now() - start > max_millis)?
+            }
+            None => self.batch_proof_within(hashes, &mut || false)?,
+        };
+
+        Ok(WasmProof { inner: proof })
+    }
+
+    // Same checks as WasmStump::verify_inner, so stale or bogus proofs get the same
+    // error kinds from either accumulator
+    fn verify_inner(
+        &self,
+        proof: &Proof<BitcoinNodeHash>,
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<bool, UtreexoError> {
+        check_not_empty(self.inner.leaves(), proof)?;
+        check_leaf_count(self.inner.leaves(), proof)?;
+        Ok(self.inner.verify(proof, del_hashes) == Ok(true))
+    }
+
+    fn modify_checked(
+        &mut self,
+        proof: Proof<BitcoinNodeHash>,
+        add_items: &[PollardAddition<BitcoinNodeHash>],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<(), UtreexoError> {
+        check_not_empty(self.inner.leaves(), &proof)?;
+        check_deletion_targets("pollard", del_hashes, &proof)?;
+        let leaves_after = grown_leaves(self.inner.leaves(), add_items.len())?;
+        check_deletion_rows(
+            &self.inner.roots(),
+            leaves_after,
+            add_items.len(),
+            del_hashes.len(),
+        )?;
+
+        // Every pollard mutation comes through here, so none skips these checks
+        self.inner
+            .modify(add_items, del_hashes, proof)
+            .map_err(|e| UtreexoError::from(format!("Failed to modify pollard: {}", e)))?;
+
+        self.after_modify(add_items, del_hashes);
+        Ok(())
+    }
+
+    fn modify_with_supplement_inner(
+        &mut self,
+        supplement: Proof<BitcoinNodeHash>,
+        add_items: &[PollardAddition<BitcoinNodeHash>],
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<(), UtreexoError> {
+        let leaves = self.inner.leaves();
+        check_not_empty(leaves, &supplement)?;
+        grown_leaves(leaves, add_items.len())?;
+        let cached: Vec<bool> = del_hashes
+            .iter()
+            .map(|hash| self.inner.prove_single(*hash).is_ok())
+            .collect();
+
+        let forgotten = cached.iter().filter(|is_cached| !**is_cached).count();
+        if forgotten != supplement.targets.len() {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::DeletionNotInProof,
+                format!(
+                    "Failed to modify pollard: {} deletions are not cached but the supplementary proof has {} targets",
+                    forgotten,
+                    supplement.targets.len()
+                ),
+            ));
+        }
+
+        let supplement_positions = proof_positions(&supplement.targets, leaves);
+        if supplement.hashes.len() < supplement_positions.len() {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::ProofVerificationFailed,
+                format!(
+                    "Failed to modify pollard: supplementary proof has {} hashes but its targets need {}",
+                    supplement.hashes.len(),
+                    supplement_positions.len()
+                ),
+            ));
+        }
+
+        let cached_hashes: Vec<BitcoinNodeHash> = del_hashes
+            .iter()
+            .zip(&cached)
+            .filter(|(_, is_cached)| **is_cached)
+            .map(|(hash, _)| *hash)
+            .collect();
+        let cached_proof = self
+            .inner
+            .batch_proof(&cached_hashes)
+            .map_err(|e| UtreexoError::from(format!("Failed to generate proof: {}", e)))?;
+
+        // A batch proof only ever needs hashes that one of the two partial proofs carries
+        let mut known: HashMap<u64, BitcoinNodeHash> =
+            proof_positions(&cached_proof.targets, leaves)
+                .into_iter()
+                .zip(cached_proof.hashes)
+                .collect();
+        known.extend(supplement_positions.into_iter().zip(supplement.hashes));
+
+        let mut cached_targets = cached_proof.targets.into_iter();
+        let mut supplement_targets = supplement.targets.into_iter();
+        let targets: Vec<u64> = cached
+            .iter()
+            .filter_map(|is_cached| {
+                if *is_cached {
+                    cached_targets.next()
+                } else {
+                    supplement_targets.next()
+                }
+            })
+            .collect();
+
+        let hashes = proof_positions(&targets, leaves)
+            .into_iter()
+            .map(|pos| {
+                known.get(&pos).copied().ok_or_else(|| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::ProofVerificationFailed,
+                        format!(
+                            "Failed to modify pollard: missing hash for position {}",
+                            pos
+                        ),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Pollard::modify applies proofs blindly, so check the merged one first
+        let proof = Proof::new(targets, hashes);
+        if !self.inner.verify(&proof, del_hashes).unwrap_or(false) {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::ProofVerificationFailed,
+                "Failed to modify pollard: proof does not verify",
+            ));
+        }
+        self.modify_checked(proof, add_items, del_hashes)
+    }
+
+    // A serialize/deserialize round trip isn't enough here: deserializing treats every
+    // node stored without nieces as a leaf, and leaves moved up by deletions can have
+    // nieces. Instead the roots are loaded on their own (which is the only way to put a
+    // root leaf in the leaf map) and a proof for the other provable leaves is ingested.
+    fn export_wallet_state_inner(
+        &self,
+        my_leaves: &[BitcoinNodeHash],
+    ) -> Result<Vec<u8>, UtreexoError> {
+        let mut seen = HashSet::new();
+        let entries = my_leaves
+            .iter()
+            .filter(|leaf| seen.insert(**leaf))
+            .map(|leaf| {
+                let mut proof = self
+                    .inner
+                    .prove_single(*leaf)
+                    .ok()
+                    .filter(|proof| self.inner.verify(proof, &[*leaf]) == Ok(true))
+                    .ok_or_else(|| {
+                        UtreexoError::from(format!("Leaf {} can't be proven by this pollard", leaf))
+                    })?;
+                // A leaf that is its own root gets a spare hash upstream
+                proof
+                    .hashes
+                    .truncate(proof_positions(&proof.targets, self.inner.leaves()).len());
+                Ok((*leaf, proof))
+            })
+            .collect::<Result<Vec<WalletEntry>, UtreexoError>>()?;
+
+        let stump = Stump {
+            leaves: self.inner.leaves(),
+            roots: self.inner.roots().into_iter().rev().collect(),
+        };
+        Ok(encode_wallet(&stump, &entries))
+    }
+
+    // Walks every stored node, see NodeDecoder, returning the length of rustreexo's
+    // encoding. Nothing but the path being read is held.
+    fn walk_nodes(&self, on_node: impl FnMut(u64, BitcoinNodeHash)) -> Result<usize, String> {
+        let mut sink = NodeSink {
+            decoder: NodeDecoder::default(),
+            on_node,
+            written: 0,
+        };
+        self.inner.serialize(&mut sink).map_err(|e| e.to_string())?;
+        Ok(sink.written)
+    }
+
+    // Remembered leaves by ascending position, found among the stored nodes without
+    // proving anything
+    fn remembered_positions(&self) -> Result<Vec<(u64, BitcoinNodeHash)>, String> {
+        let mut remembered = Vec::new();
+        self.walk_nodes(|pos, hash| {
+            if self.remembered.contains(&hash) {
+                remembered.push((pos, hash));
+            }
+        })?;
+        remembered.sort_unstable_by_key(|(pos, _)| *pos);
+        Ok(remembered)
+    }
+
+    // The `count` lowest of remembered_positions, keeping no more than that many while
+    // walking the nodes
+    fn first_remembered(&self, count: usize) -> Result<Vec<(u64, BitcoinNodeHash)>, String> {
+        let mut lowest = BinaryHeap::new();
+        self.walk_nodes(|pos, hash| {
+            if self.remembered.contains(&hash) {
+                lowest.push((pos, hash));
+                if lowest.len() > count {
+                    lowest.pop();
+                }
+            }
+        })?;
+        Ok(lowest.into_sorted_vec())
+    }
+
+    // remembered_positions with each leaf's proof, taken from the stored nodes: a node
+    // is only ever stored along with its sibling and the path above it, so none is
+    // missing. rebuild_pollard checks them against the roots.
+    fn remembered_inner(
+        &self,
+    ) -> Result<Vec<(u64, BitcoinNodeHash, Proof<BitcoinNodeHash>)>, String> {
+        let leaves = self.inner.leaves();
+        let mut stored = HashMap::new();
+        let mut remembered = Vec::new();
+        self.walk_nodes(|pos, hash| {
+            stored.insert(pos, hash);
+            if self.remembered.contains(&hash) {
+                remembered.push((pos, hash));
+            }
+        })?;
+        remembered.sort_unstable_by_key(|(pos, _)| *pos);
+
+        remembered
+            .into_iter()
+            .map(|(pos, hash)| {
+                let hashes = proof_positions(&[pos], leaves)
+                    .into_iter()
+                    .map(|pos| {
+                        stored
+                            .get(&pos)
+                            .copied()
+                            .ok_or_else(|| format!("node {} is missing", pos))
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok((pos, hash, Proof::new(vec![pos], hashes)))
+            })
+            .collect()
+    }
+
+    fn self_check_inner(&self) -> Result<(), UtreexoError> {
+        let leaves = self.inner.leaves();
+        checked_stump(leaves, self.inner.roots())?;
+
+        let nodes: HashMap<u64, BitcoinNodeHash> = self.nodes_inner()?.into_iter().collect();
+
+        // Every stored pair of siblings has to hash to their stored parent, which
+        // covers each remembered path up to its root
+        let forest_rows = tree_rows(leaves);
+        for (&pos, &hash) in nodes.iter().filter(|(pos, _)| *pos & 1 == 0) {
+            let parent_hash = match nodes.get(&(pos | 1)) {
+                Some(sibling) => AccumulatorHash::parent_hash(&hash, sibling),
+                None => continue,
+            };
+            let parent_pos = parent(pos, forest_rows);
+            if nodes
+                .get(&parent_pos)
+                .is_some_and(|stored| *stored != parent_hash)
+            {
+                return Err(UtreexoError::new(
+                    UtreexoErrorKind::CorruptState,
+                    format!(
+                        "Nodes {} and {} don't hash to their parent {}",
+                        pos,
+                        pos | 1,
+                        parent_pos
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn nodes_inner(&self) -> Result<Vec<(u64, BitcoinNodeHash)>, UtreexoError> {
+        let mut nodes = Vec::new();
+        self.walk_nodes(|pos, hash| nodes.push((pos, hash)))
+            .map_err(|e| UtreexoError::from(format!("Failed to read pollard nodes: {}", e)))?;
+        Ok(nodes)
+    }
+
+    fn compact_inner(&mut self) -> Result<(), UtreexoError> {
+        let compact_error =
+            |e: String| UtreexoError::from(format!("Failed to compact pollard: {}", e));
+
+        let remembered = self.remembered_inner().map_err(compact_error)?;
+        let hashes = remembered.iter().map(|(_, hash, _)| *hash).collect();
+        self.inner = rebuild_pollard(self.inner.leaves(), self.inner.roots(), remembered)
+            .map_err(compact_error)?;
+        self.remembered = hashes;
+        Ok(())
+    }
+
+    // Upstream Pollard::prune clears a node's nieces, which can take a sibling's path
+    // along with it, so rebuild from the leaves that stay remembered instead
+    fn prune_inner(&mut self, forget: &[BitcoinNodeHash]) -> Result<(), UtreexoError> {
+        let prune_error = |e: String| UtreexoError::from(format!("Failed to prune pollard: {}", e));
+
+        let mut remembered = self.remembered_inner().map_err(prune_error)?;
+        if let Some(leaf) = forget
+            .iter()
+            .find(|leaf| !remembered.iter().any(|(_, hash, _)| hash == *leaf))
+        {
+            return Err(UtreexoError::from(format!(
+                "Leaf {} isn't remembered by this pollard",
+                leaf
+            )));
+        }
+
+        remembered.retain(|(_, hash, _)| !forget.contains(hash));
+        let hashes = remembered.iter().map(|(_, hash, _)| *hash).collect();
+        self.inner = rebuild_pollard(self.inner.leaves(), self.inner.roots(), remembered)
+            .map_err(prune_error)?;
+        self.remembered = hashes;
+        for leaf in forget {
+            self.leaf_data.remove(leaf);
+        }
+        Ok(())
+    }
+
+    fn missing_proof_targets_inner(&self, del_hashes: &[BitcoinNodeHash]) -> Vec<BitcoinNodeHash> {
+        del_hashes
+            .iter()
+            .copied()
+            .filter(|&hash| {
+                !self.inner.prove_single(hash).map_or(false, |proof| {
+                    self.inner.verify(&proof, &[hash]) == Ok(true)
+                })
+            })
+            .collect()
+    }
+
+    fn delete_inner(&mut self, del_hashes: &[BitcoinNodeHash]) -> Result<Vec<u64>, UtreexoError> {
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = del_hashes.iter().find(|hash| !seen.insert(**hash)) {
+            return Err(UtreexoError::from(format!(
+                "Leaf {} is deleted more than once",
+                duplicate
+            )));
+        }
+
+        let proof = self.deletion_proof(del_hashes)?;
+        let positions = proof.targets.clone();
+        self.modify_checked(proof, &[], del_hashes)?;
+        Ok(positions)
+    }
+
+    // Batch proofs from a churned pollard don't always verify, so this merges checked
+    // single proofs instead
+    fn deletion_proof(
+        &self,
+        del_hashes: &[BitcoinNodeHash],
+    ) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+        let proofs = del_hashes
+            .iter()
+            .map(|&hash| {
+                let proof = self.inner.prove_single(hash).map_err(|e| {
+                    UtreexoError::new(
+                        UtreexoErrorKind::DeletionNotInProof,
+                        format!("Leaf {} is not remembered: {}", hash, e),
+                    )
+                })?;
+                if !self.inner.verify(&proof, &[hash]).unwrap_or(false) {
+                    return Err(UtreexoError::new(
+                        UtreexoErrorKind::ProofVerificationFailed,
+                        format!("Proof for leaf {} does not verify", hash),
+                    ));
+                }
+                Ok(proof)
+            })
+            .collect::<Result<Vec<_>, UtreexoError>>()?;
+        merge_proofs_inner(&proofs, self.inner.leaves())
+    }
+
+    fn merkle_path_inner(&self, leaf: BitcoinNodeHash) -> Result<Vec<PathStep>, UtreexoError> {
+        let proof = self
+            .inner
+            .prove_single(leaf)
+            .map_err(|e| UtreexoError::from(format!("Leaf {} is not remembered: {}", leaf, e)))?;
+        let leaves = self.inner.leaves();
+        let forest_rows = tree_rows(leaves);
+
+        let mut pos = proof.targets[0];
+        let mut hash = leaf;
+        let mut path = Vec::new();
+        for (sibling_pos, sibling) in proof_positions(&proof.targets, leaves)
+            .into_iter()
+            .zip(proof.hashes)
+        {
+            if sibling_pos != pos ^ 1 {
+                return Err(UtreexoError::from(format!(
+                    "Proof for leaf {} is missing the sibling of {}",
+                    leaf, pos
+                )));
+            }
+            let is_left = pos & 1 == 0;
+            path.push(PathStep {
+                hash: hex(&hash),
+                sibling: Some(hex(&sibling)),
+                is_left: Some(is_left),
+            });
+            hash = if is_left {
+                BitcoinNodeHash::parent_hash(&hash, &sibling)
+            } else {
+                BitcoinNodeHash::parent_hash(&sibling, &hash)
+            };
+            pos = parent(pos, forest_rows);
+        }
+
+        if !self.inner.roots().contains(&hash) {
+            return Err(UtreexoError::new(
+                UtreexoErrorKind::ProofVerificationFailed,
+                format!(
+                    "Path for leaf {} ends at {}, which is not a root",
+                    leaf, hash
+                ),
+            ));
+        }
+        path.push(PathStep {
+            hash: hex(&hash),
+            sibling: None,
+            is_left: None,
+        });
+        Ok(path)
+    }
+
+    // Only remembered leaves carry data and prune drops it itself, so deletions are
+    // all leaf data has to follow here. Deletions go before additions, as in modify.
+    fn after_modify(
+        &mut self,
+        add_items: &[PollardAddition<BitcoinNodeHash>],
+        del_hashes: &[BitcoinNodeHash],
+    ) {
+        for hash in del_hashes {
+            self.leaf_data.remove(hash);
+            self.remembered.remove(hash);
+        }
+        self.remembered
+            .extend(add_items.iter().map(|addition| addition.hash));
+        self.notify_roots_changed();
+    }
+
+    fn notify_roots_changed(&self) {
+        if let Some(cb) = &self.roots_changed {
+            let roots: js_sys::Array = self.roots().into_iter().collect();
+            // The modify already went through, so a throwing callback can't undo it
+            let _ = cb.call1(&JsValue::NULL, &roots);
+        }
+    }
+}
+
+// Utility functions
+#[wasm_bindgen]
+pub fn version() -> String {
     env!("CARGO_PKG_VERSION").to_string()
 }
+
+// Version of the rustreexo dependency this build was linked against
+#[wasm_bindgen]
+pub fn rustreexo_version() -> String {
+    env!("RUSTREEXO_VERSION").to_string()
+}
+
+// The digest behind every accumulator hash: parents are sha512_256(left || right),
+// the FIPS SHA-512/256 variant, and every hash (leaf, node or root) is 32 bytes
+#[wasm_bindgen]
+pub fn hash_info() -> JsValue {
+    let info = serde_json::json!({
+        "algo": "sha512_256",
+        "byte_len": 32,
+    });
+
+    to_js(&info).unwrap_or(JsValue::NULL)
+}
+
+// One-off commitment to a list of hashes: they're added to an empty stump in order
+// and its roots folded into a single value exactly like WasmStump::state_hash does,
+// so the leaf count is committed to as well. A single leaf isn't its own root here.
+#[wasm_bindgen]
+pub fn merkle_root(leaves: Vec<JsValue>) -> Result<Hash, UtreexoError> {
+    let leaves = parse_hashes(leaves)?;
+    merkle_root_inner(&leaves)
+}
+
+fn merkle_root_inner(leaves: &[BitcoinNodeHash]) -> Result<Hash, UtreexoError> {
+    let mut stump = Stump::new();
+    modify_stump(&mut stump, &Proof::default(), leaves, &[])?;
+    Ok(Hash {
+        inner: state_hash(stump.leaves, &stump.roots),
+    })
+}
+
+// Heap usage and live accumulator handles, for spotting handles JS never freed.
+// heap_used counts bytes currently allocated; heap_total is the size of the wasm
+// linear memory (0 off wasm).
+#[wasm_bindgen]
+pub fn wasm_memory_stats() -> JsValue {
+    #[cfg(target_arch = "wasm32")]
+    let heap_total = core::arch::wasm32::memory_size(0) * 65536;
+    #[cfg(not(target_arch = "wasm32"))]
+    let heap_total = 0;
+
+    let stats = serde_json::json!({
+        "heap_used": crate::heap_used(),
+        "heap_total": heap_total,
+        "live_stumps": LIVE_STUMPS.load(Ordering::Relaxed),
+        "live_pollards": LIVE_POLLARDS.load(Ordering::Relaxed),
+    });
+
+    to_js(&stats).unwrap_or(JsValue::NULL)
+}
+
+// Applies to every hex string handed to JS from then on
+#[wasm_bindgen]
+pub fn set_hex_uppercase(uppercase: bool) {
+    HEX_UPPERCASE.with(|flag| flag.set(uppercase));
+}
+
+// The ordering rules the accumulator follows; nothing in it depends on randomness,
+// so the same ops always produce the same roots and proofs
+#[wasm_bindgen]
+pub fn ordering_info() -> JsValue {
+    let info = serde_json::json!({
+        "leaf_positions": "Leaves are numbered in insertion order; a new leaf takes position num_leaves",
+        "node_positions": "Row 0 holds positions 0..2^forest_rows, and the parent of pos is (pos >> 1) | (1 << forest_rows), with forest_rows = ceil(log2(num_leaves))",
+        "sibling_order": "The even position is the left child and pos ^ 1 is its sibling; parent = sha512_256(left || right)",
+        "deletion": "A deleted node becomes empty and its sibling moves up to take the parent's position",
+        "target_order": "Targets are paired with deletion hashes by index, then all nodes are sorted by ascending position before hashing, so target order doesn't change the result",
+        "proof_hash_order": "Proof hashes are listed by ascending position",
+        "stump_roots": "WasmStump roots are ordered from the biggest tree to the smallest",
+        "pollard_roots": "WasmPollard roots are ordered from the smallest tree to the biggest",
+    });
+
+    to_js(&info).unwrap_or(JsValue::NULL)
+}
+
+// Merges two proofs over the same accumulator into one batch proof. Targets keep
+// proof_a's order followed by proof_b's new ones, which is the order deletion
+// hashes must be passed in.
+#[wasm_bindgen]
+pub fn merge_proofs(proof_a: &str, proof_b: &str, num_leaves: u64) -> Result<String, UtreexoError> {
+    let proof_a = parse_proof(proof_a)?;
+    let proof_b = parse_proof(proof_b)?;
+
+    let merged = merge_proofs_inner(&[proof_a, proof_b], num_leaves)?;
+    serde_json::to_string(&merged)
+        .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+}
+
+// A fresh pollard holding just the given roots (smallest tree first, like
+// Pollard::roots) and the remembered leaves, each with a verified single proof
+fn rebuild_pollard(
+    leaves: u64,
+    roots: Vec<BitcoinNodeHash>,
+    remembered: Vec<(u64, BitcoinNodeHash, Proof<BitcoinNodeHash>)>,
+) -> Result<Pollard<BitcoinNodeHash>, String> {
+    let forest_rows = tree_rows(leaves);
+    let stump = Stump {
+        leaves,
+        roots: roots.iter().rev().copied().collect(),
+    };
+    let (root_leaves, inner_leaves): (Vec<_>, Vec<_>) = remembered
+        .into_iter()
+        .partition(|(pos, _, _)| is_root_position(*pos, leaves, forest_rows));
+
+    let mut roots_only = leaves.to_be_bytes().to_vec();
+    let mut roots = roots.into_iter();
+    for row in 0..64 {
+        let root = match leaves & (1 << row) {
+            0 => None,
+            _ => roots.next(),
+        };
+        match root {
+            // A root marker, then the root as a node without nieces
+            Some(root) => {
+                roots_only.extend([1, 1]);
+                let _ = root.write(&mut roots_only);
+            }
+            None => roots_only.push(0),
+        }
+    }
+    let mut pollard =
+        Pollard::deserialize(&mut roots_only.as_slice()).map_err(|e| e.to_string())?;
+    for row in (0..64u8).filter(|row| leaves & (1 << row) != 0) {
+        let pos = root_position(leaves, row, forest_rows);
+        if !root_leaves.iter().any(|(root, _, _)| *root == pos) {
+            pollard.prune(&[pos]).map_err(|e| e.to_string())?;
+        }
+    }
+
+    // Batch proofs from a churned pollard don't always verify, so merge the checked
+    // single proofs. They have to be ingested in one go, as ingesting replaces the
+    // roots of the trees a proof touches.
+    if !inner_leaves.is_empty() {
+        let hashes: Vec<BitcoinNodeHash> = inner_leaves.iter().map(|(_, hash, _)| *hash).collect();
+        let proofs: Vec<Proof<BitcoinNodeHash>> = inner_leaves
+            .iter()
+            .map(|(_, _, proof)| proof.clone())
+            .collect();
+        let proof = merge_proofs_inner(&proofs, leaves).map_err(|e| e.message)?;
+        // Ingesting takes the hashes on trust and replaces the roots with what they
+        // hash up to, so check them against the roots first
+        if stump.verify(&proof, &hashes) != Ok(true) {
+            return Err("the remembered leaves' paths don't hash up to the roots".to_string());
+        }
+        let targets = proof.targets.clone();
+        pollard.ingest_proof(proof, &hashes, &targets)?;
+    }
+
+    // Ingesting makes a leaf's sibling provable too; forget the ones that weren't
+    let positions: HashSet<u64> = inner_leaves.iter().map(|(pos, _, _)| *pos).collect();
+    for sibling in positions.iter().map(|pos| pos ^ 1) {
+        if !positions.contains(&sibling) {
+            pollard.prune(&[sibling]).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(pollard)
+}
+
+fn merge_proofs_inner(
+    proofs: &[Proof<BitcoinNodeHash>],
+    num_leaves: u64,
+) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    check_leaves(num_leaves)?;
+    let mut targets: Vec<u64> = Vec::new();
+    let mut known: HashMap<u64, BitcoinNodeHash> = HashMap::new();
+
+    for (index, proof) in proofs.iter().enumerate() {
+        let positions = proof_positions(&proof.targets, num_leaves);
+        if proof.hashes.len() < positions.len() {
+            return Err(UtreexoError::from(format!(
+                "Proof {} has {} hashes but its targets need {}",
+                index,
+                proof.hashes.len(),
+                positions.len()
+            )));
+        }
+
+        // Proofs generated against different states show up as different hashes
+        // for the same position; hashes only one proof carries can't be cross-checked
+        for (pos, hash) in positions.into_iter().zip(proof.hashes.iter().copied()) {
+            if let Some(existing) = known.insert(pos, hash) {
+                if existing != hash {
+                    return Err(UtreexoError::new(
+                        UtreexoErrorKind::ProofConflict,
+                        format!(
+                            "Proof {} conflicts at position {}: {} vs {}",
+                            index, pos, hash, existing
+                        ),
+                    ));
+                }
+            }
+        }
+
+        for target in &proof.targets {
+            if !targets.contains(target) {
+                targets.push(*target);
+            }
+        }
+    }
+
+    // Every position the merged proof needs was already needed by one of the inputs
+    let hashes = proof_positions(&targets, num_leaves)
+        .into_iter()
+        .map(|pos| {
+            known
+                .get(&pos)
+                .copied()
+                .ok_or_else(|| UtreexoError::from(format!("Missing hash for position {}", pos)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Proof::new(targets, hashes))
+}
+
+// Proofs carry one hash per needed position in position order, so target order is the
+// only thing two encodings of the same proof can differ in
+fn canonical_parts(proof: &Proof<BitcoinNodeHash>) -> (Vec<u64>, Vec<BitcoinNodeHash>) {
+    let mut targets = proof.targets.clone();
+    targets.sort_unstable();
+    (targets, proof.hashes.clone())
+}
+
+#[wasm_bindgen]
+pub fn proofs_equivalent(a_json: &str, b_json: &str) -> Result<bool, UtreexoError> {
+    let a = parse_proof(a_json)?;
+    let b = parse_proof(b_json)?;
+    Ok(canonical_parts(&a) == canonical_parts(&b))
+}
+
+// Compares two root lists in order after dropping the empty roots from both, whether
+// written as "empty" (how roots() prints them) or as the 32 zero byte empty hash
+#[wasm_bindgen]
+pub fn roots_equivalent(a: Vec<JsValue>, b: Vec<JsValue>) -> Result<bool, UtreexoError> {
+    fn non_empty(roots: Vec<JsValue>) -> Result<Vec<BitcoinNodeHash>, UtreexoError> {
+        let mut hashes = Vec::with_capacity(roots.len());
+        for root in roots {
+            let hex = root.as_string().ok_or_else(|| {
+                UtreexoError::new(UtreexoErrorKind::InvalidHash, "Root hash must be a string")
+            })?;
+            if hex == "empty" {
+                continue;
+            }
+            let hash = BitcoinNodeHash::from_str(&hex).map_err(|e| {
+                UtreexoError::new(
+                    UtreexoErrorKind::InvalidHash,
+                    format!("Invalid root hash: {}", e),
+                )
+            })?;
+            if hash.iter().any(|byte| *byte != 0) {
+                hashes.push(hash);
+            }
+        }
+        Ok(hashes)
+    }
+
+    Ok(non_empty(a)? == non_empty(b)?)
+}
+
+// Commitment to an accumulator state: the leaf count (LE, zero padded) with each
+// root, biggest tree first, folded in through parent_hash
+fn state_hash(leaves: u64, roots: &[BitcoinNodeHash]) -> BitcoinNodeHash {
+    let mut count = [0u8; 32];
+    count[..8].copy_from_slice(&leaves.to_le_bytes());
+    roots
+        .iter()
+        .fold(BitcoinNodeHash::new(count), |state, root| {
+            BitcoinNodeHash::parent_hash(&state, root)
+        })
+}
+
+// Checkpoint preimages carry their own version, apart from FORMAT_VERSION, so they
+// never change along with the storage formats
+const CHECKPOINT_VERSION: u8 = 1;
+
+// digest of: "utreexo-checkpoint", the version byte, the hash algorithm name behind a
+// length byte, the LE u64 leaf count, the LE u64 root count and the roots' 32 bytes,
+// biggest tree first. Unlike state_hash it names version and algorithm, so states
+// can't collide across formats.
+fn checkpoint_digest(leaves: u64, roots: &[BitcoinNodeHash]) -> BitcoinNodeHash {
+    const ALGO: &str = "sha512_256";
+    let mut preimage = b"utreexo-checkpoint".to_vec();
+    preimage.push(CHECKPOINT_VERSION);
+    preimage.push(ALGO.len() as u8);
+    preimage.extend_from_slice(ALGO.as_bytes());
+    preimage.extend(leaves.to_le_bytes());
+    preimage.extend((roots.len() as u64).to_le_bytes());
+    for root in roots {
+        preimage.extend_from_slice(&**root);
+    }
+    digest(&preimage)
+}
+
+// Prefixed to every digest preimage, so no digest can pass for an accumulator node
+// (whose preimage is two bare hashes) or a leaf hash
+const DIGEST_TAG: &[u8] = b"rustreexo-wasm/digest";
+
+// sha512_256(DIGEST_TAG || bytes) in one pass. Checkpoint digests are published and
+// compared later, so this must never change.
+fn digest(bytes: &[u8]) -> BitcoinNodeHash {
+    let mut engine = sha512_256::Hash::engine();
+    engine.input(DIGEST_TAG);
+    engine.input(bytes);
+    BitcoinNodeHash::new(sha512_256::Hash::from_engine(engine).to_byte_array())
+}
+
+// Whether the bundle was generated against exactly the stump's current state, so it
+// still applies as is. Bundles built by hand carry no state and are never fresh.
+#[wasm_bindgen]
+pub fn is_proof_fresh(bundle: &WasmProofBundle, current: &WasmStump) -> bool {
+    let stump = &current.inner;
+    bundle.valid_at == Some((stump.leaves, state_hash(stump.leaves, &stump.roots)))
+}
+
+// Generic merkle multiproof layout (as in OpenZeppelin's MerkleProof): walking the
+// known nodes bottom-up, each flag tells whether a node's sibling is the next known
+// node (true) or the next proof hash (false)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Multiproof {
+    leaves_positions: Vec<u64>,
+    proof_hashes: Vec<String>,
+    flags: Vec<bool>,
+}
+
+// Flags for proving `targets`, ascending, in a forest of `num_leaves`. Reaching a
+// root ends that tree's walk and takes no flag, so a forest has one "final root"
+// per tree a target is in, not a single one.
+fn multiproof_flags(targets: &[u64], num_leaves: u64) -> Vec<bool> {
+    let forest_rows = tree_rows(num_leaves);
+    let mut known: BTreeSet<u64> = targets.iter().copied().collect();
+    let mut flags = Vec::new();
+
+    while let Some(pos) = known.pop_first() {
+        if is_root_position(pos, num_leaves, forest_rows) {
+            continue;
+        }
+
+        let sibling_known = known.first() == Some(&(pos ^ 1));
+        if sibling_known {
+            known.pop_first();
+        }
+        flags.push(sibling_known);
+        known.insert(parent(pos, forest_rows));
+    }
+
+    flags
+}
+
+fn proof_to_multiproof_inner(
+    proof: &Proof<BitcoinNodeHash>,
+    num_leaves: u64,
+) -> Result<Multiproof, UtreexoError> {
+    check_not_empty(num_leaves, proof)?;
+    check_leaf_count(num_leaves, proof)?;
+
+    // Proof hashes are stored by ascending position, which is also the order the
+    // bottom-up walk asks for them in. Multiproofs are a serialization format, so
+    // their hex stays lowercase whatever set_hex_uppercase picked.
+    let mut targets = proof.targets.clone();
+    targets.sort_unstable();
+    let needed = proof_positions(&targets, num_leaves).len();
+
+    Ok(Multiproof {
+        flags: multiproof_flags(&targets, num_leaves),
+        proof_hashes: proof.hashes[..needed]
+            .iter()
+            .map(|hash| hash.to_string())
+            .collect(),
+        leaves_positions: targets,
+    })
+}
+
+fn multiproof_to_proof_inner(
+    multiproof: &Multiproof,
+    num_leaves: u64,
+) -> Result<Proof<BitcoinNodeHash>, UtreexoError> {
+    check_leaves(num_leaves)?;
+    let targets = &multiproof.leaves_positions;
+    if targets.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(UtreexoError::from(
+            "Multiproof leaf positions must be strictly ascending",
+        ));
+    }
+
+    let proof = Proof::new(targets.clone(), parse_hex_hashes(&multiproof.proof_hashes)?);
+    check_not_empty(num_leaves, &proof)?;
+    check_leaf_count(num_leaves, &proof)?;
+
+    let needed = proof_positions(targets, num_leaves).len();
+    if proof.hashes.len() != needed || multiproof.flags != multiproof_flags(targets, num_leaves) {
+        return Err(UtreexoError::from(format!(
+            "The multiproof's hashes and flags don't match its leaf positions in an accumulator of {} leaves",
+            num_leaves
+        )));
+    }
+    Ok(proof)
+}
+
+// Converts a utreexo proof to a {leaves_positions, proof_hashes, flags} multiproof
+// for an accumulator of `num_leaves`. Parents are still hashed in left/right order,
+// not sorted as some multiproof verifiers do.
+#[wasm_bindgen]
+pub fn proof_to_multiproof(proof_json: &str, num_leaves: u64) -> Result<JsValue, UtreexoError> {
+    let proof = parse_proof(proof_json)?;
+    to_js(&proof_to_multiproof_inner(&proof, num_leaves)?)
+}
+
+// Reverse of proof_to_multiproof, taking the multiproof as JSON
+#[wasm_bindgen]
+pub fn multiproof_to_proof(multiproof_json: &str, num_leaves: u64) -> Result<String, UtreexoError> {
+    let multiproof: Multiproof = serde_json::from_str(multiproof_json).map_err(|e| {
+        UtreexoError::new(
+            UtreexoErrorKind::InvalidJson,
+            format!("Failed to parse multiproof JSON: {}", e),
+        )
+    })?;
+    let proof = multiproof_to_proof_inner(&multiproof, num_leaves)?;
+
+    serde_json::to_string(&proof)
+        .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+}
+
+// Whether the proof is valid under each of the stumps (given as their to_json
+// output), e.g. competing chain tips. A proof built for another leaf count is just
+// invalid there rather than an error.
+#[wasm_bindgen]
+pub fn verify_against_many(
+    stumps: Vec<JsValue>,
+    proof_json: &str,
+    hashes: Vec<JsValue>,
+) -> Result<Vec<JsValue>, UtreexoError> {
+    let stumps = stumps
+        .into_iter()
+        .map(|js_val| {
+            let json = js_val.as_string().ok_or_else(|| {
+                UtreexoError::new(UtreexoErrorKind::InvalidJson, "Stump must be a JSON string")
+            })?;
+            Ok(parse_stump_record(&json)?.stump)
+        })
+        .collect::<Result<Vec<Stump>, UtreexoError>>()?;
+    let proof = parse_proof(proof_json)?;
+    let del_hashes = parse_hashes(hashes)?;
+
+    Ok(verify_against_many_inner(&stumps, &proof, &del_hashes)
+        .into_iter()
+        .map(JsValue::from_bool)
+        .collect())
+}
+
+fn verify_against_many_inner(
+    stumps: &[Stump],
+    proof: &Proof<BitcoinNodeHash>,
+    del_hashes: &[BitcoinNodeHash],
+) -> Vec<bool> {
+    stumps
+        .iter()
+        .map(|stump| {
+            check_not_empty(stump.leaves, proof).is_ok()
+                && check_leaf_count(stump.leaves, proof).is_ok()
+                && stump.verify(proof, del_hashes) == Ok(true)
+        })
+        .collect()
+}
+
+// Update proof from `old` to where replaying the op log takes it, for
+// WasmStump::apply_update_proof. The log is replayed first, so invalid ops fail here.
+#[wasm_bindgen]
+pub fn compute_update_proof(old: &WasmStump, ops_json: &str) -> Result<String, UtreexoError> {
+    let record = compute_update_proof_inner(old, parse_ops(ops_json)?)?;
+    serde_json::to_string(&record)
+        .map_err(|e| UtreexoError::from(format!("Failed to serialize update proof: {}", e)))
+}
+
+fn compute_update_proof_inner(
+    old: &WasmStump,
+    ops: Vec<OpLogEntry>,
+) -> Result<UpdateProofRecord, UtreexoError> {
+    let mut new = old.inner.clone();
+    apply_stump_ops(&mut new, &ops)?;
+
+    Ok(UpdateProofRecord {
+        from_leaves: old.inner.leaves,
+        from_state: state_hash(old.inner.leaves, &old.inner.roots).to_string(),
+        to: new,
+        steps: fold_additions(ops),
+    })
+}
+
+// Verifies against a past state given by its roots (biggest tree first, like
+// WasmStump::roots) and leaf count, leaving every live accumulator alone. Errors are
+// the same as WasmStump::verify's, plus InconsistentRoots for a bad root count.
+#[wasm_bindgen]
+pub fn verify_historical(
+    roots: Vec<JsValue>,
+    leaves: u64,
+    proof_json: &str,
+    hashes: Vec<JsValue>,
+) -> Result<bool, UtreexoError> {
+    let historical = checked_stump(leaves, parse_hashes(roots)?)?;
+    verify_stump(
+        &historical,
+        &parse_proof(proof_json)?,
+        &parse_hashes(hashes)?,
+    )
+}
+
+#[wasm_bindgen]
+pub fn proof_to_bytes(proof_json: &str) -> Result<Vec<u8>, UtreexoError> {
+    Ok(encode_proof(&parse_proof(proof_json)?))
+}
+
+#[wasm_bindgen]
+pub fn proof_from_bytes(bytes: &[u8]) -> Result<String, UtreexoError> {
+    let proof = decode_proof(&migrate_inner(bytes)?)?;
+    serde_json::to_string(&proof)
+        .map_err(|e| UtreexoError::from(format!("Failed to serialize proof: {}", e)))
+}
+
+// Upgrades a blob written by any earlier format (including pre-versioning JSON) to
+// the current binary format
+#[wasm_bindgen]
+pub fn migrate(bytes: &[u8]) -> Result<Vec<u8>, UtreexoError> {
+    migrate_inner(bytes)
+}
+
+#[wasm_bindgen]
+pub fn proof_targets(proof_json: &str) -> Result<Vec<u64>, UtreexoError> {
+    Ok(parse_proof(proof_json)?.targets)
+}
+
+// The leaf positions the proof can prove, ascending and without repeats, for
+// indexing proofs by coverage; proof_targets keeps the proof's own order instead
+#[wasm_bindgen]
+pub fn proof_provable_positions(proof_json: &str) -> Result<Vec<u64>, UtreexoError> {
+    let targets: BTreeSet<u64> = parse_proof(proof_json)?.targets.into_iter().collect();
+    Ok(targets.into_iter().collect())
+}
+
+// Remember flags as a bitset: flag i is bit i % 8 of byte i / 8, least significant
+// bit first, with the unused bits of the last byte left zero
+#[wasm_bindgen]
+pub fn pack_remember_flags(flags: Vec<JsValue>) -> Result<Vec<u8>, UtreexoError> {
+    let flags = flags
+        .into_iter()
+        .map(|js_val| {
+            js_val
+                .as_bool()
+                .ok_or_else(|| UtreexoError::from("Remember flag must be a boolean"))
+        })
+        .collect::<Result<Vec<bool>, UtreexoError>>()?;
+    Ok(pack_flags(&flags))
+}
+
+// The first `count` flags of a pack_remember_flags bitset
+#[wasm_bindgen]
+pub fn unpack_remember_flags(packed: &[u8], count: usize) -> Result<Vec<JsValue>, UtreexoError> {
+    Ok(unpack_flags(packed, count)?
+        .into_iter()
+        .map(JsValue::from_bool)
+        .collect())
+}
+
+fn pack_flags(flags: &[bool]) -> Vec<u8> {
+    let mut packed = vec![0u8; (flags.len() + 7) / 8];
+    for (i, _) in flags.iter().enumerate().filter(|(_, flag)| **flag) {
+        packed[i / 8] |= 1 << (i % 8);
+    }
+    packed
+}
+
+fn unpack_flags(packed: &[u8], count: usize) -> Result<Vec<bool>, UtreexoError> {
+    let expected = (count + 7) / 8;
+    if packed.len() != expected {
+        return Err(UtreexoError::from(format!(
+            "{} flags take {} bytes, got {}",
+            count,
+            expected,
+            packed.len()
+        )));
+    }
+    if count % 8 != 0 && packed[expected - 1] >> (count % 8) != 0 {
+        return Err(UtreexoError::from(format!(
+            "Bits past the {} flags are set",
+            count
+        )));
+    }
+    Ok((0..count)
+        .map(|i| packed[i / 8] >> (i % 8) & 1 == 1)
+        .collect())
+}
+
+// Counts the targets without collecting them or decoding any hash; the rest of the
+// JSON is only checked for syntax
+#[wasm_bindgen]
+pub fn proof_target_count(proof_json: &str) -> Result<usize, UtreexoError> {
+    struct Count(usize);
+
+    impl<'de> Deserialize<'de> for Count {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Count, D::Error> {
+            struct CountVisitor;
+
+            impl<'de> serde::de::Visitor<'de> for CountVisitor {
+                type Value = Count;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("an array of target positions")
+                }
+
+                fn visit_seq<A: serde::de::SeqAccess<'de>>(
+                    self,
+                    mut seq: A,
+                ) -> Result<Count, A::Error> {
+                    let mut count = 0;
+                    while seq.next_element::<u64>()?.is_some() {
+                        count += 1;
+                    }
+                    Ok(Count(count))
+                }
+            }
+
+            deserializer.deserialize_seq(CountVisitor)
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct Targets {
+        targets: Count,
+    }
+
+    serde_json::from_str::<Targets>(proof_json)
+        .map(|proof| proof.targets.0)
+        .map_err(|e| {
+            UtreexoError::new(
+                UtreexoErrorKind::InvalidJson,
+                format!("Failed to parse proof JSON: {}", e),
+            )
+        })
+}
+
+// In the order the proof carries them, for reconstructing roots independently
+#[wasm_bindgen]
+pub fn proof_hashes(proof_json: &str) -> Result<Vec<JsValue>, UtreexoError> {
+    Ok(parse_proof(proof_json)?
+        .hashes
+        .into_iter()
+        .map(|hash| JsValue::from(Hash { inner: hash }.to_hex()))
+        .collect())
+}
+
+#[wasm_bindgen]
+pub fn estimate_proof_hashes(positions: Vec<u64>, num_leaves: u64) -> Result<usize, UtreexoError> {
+    check_leaves(num_leaves)?;
+    let mut seen = HashSet::new();
+    for &pos in &positions {
+        if pos >= num_leaves {
+            return Err(UtreexoError::from(format!(
+                "Position {} is out of range for {} leaves",
+                pos, num_leaves
+            )));
+        }
+        if !seen.insert(pos) {
+            return Err(UtreexoError::from(format!("Duplicate position {}", pos)));
+        }
+    }
+
+    Ok(proof_positions(&positions, num_leaves).len())
+}
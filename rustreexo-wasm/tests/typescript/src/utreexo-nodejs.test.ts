@@ -5,6 +5,7 @@
  * CI/CD testing environments.
  */
 import { describe, it, expect, beforeAll, afterEach } from 'vitest';
+import * as crypto from 'crypto';
 
 // Import the Node.js WASM module directly
 let wasmModule: any;
@@ -41,6 +42,38 @@ describe('Rustreexo WASM Node.js Tests', () => {
       expect(version).toBe('0.4.0');
     });
 
+    it('should return the rustreexo dependency version', () => {
+      expect(wasmModule.rustreexo_version()).toMatch(/^0\.4\.\d+$/);
+    });
+
+    it('should describe the accumulator hash function', () => {
+      const info = wasmModule.hash_info();
+      expect(info.algo).toBe('sha512_256');
+      expect(info.byte_len).toBe(32);
+
+      const hash = new wasmModule.Hash('00'.repeat(32));
+      wasmObjects.push(hash);
+      expect(hash.to_bytes().length).toBe(info.byte_len);
+    });
+
+    it('should count live accumulator handles', () => {
+      const before = wasmModule.wasm_memory_stats();
+      expect(before.heap_total).toBeGreaterThan(0);
+      expect(before.heap_used).toBeGreaterThan(0);
+
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      const during = wasmModule.wasm_memory_stats();
+      expect(during.live_stumps).toBe(before.live_stumps + 1);
+      expect(during.live_pollards).toBe(before.live_pollards + 1);
+
+      stump.free();
+      pollard.free();
+      const after = wasmModule.wasm_memory_stats();
+      expect(after.live_stumps).toBe(before.live_stumps);
+      expect(after.live_pollards).toBe(before.live_pollards);
+    });
+
     it('should create and manage Hash objects', () => {
       const hexString = 'deadbeefcafebabe0123456789abcdef0123456789abcdef0123456789abcdef';
       const hash = new wasmModule.Hash(hexString);
@@ -57,6 +90,21 @@ describe('Rustreexo WASM Node.js Tests', () => {
       expect(reconstructed.to_hex()).toBe(hexString);
     });
 
+    it('should convert to and from display byte order', () => {
+      // Internal order on the left, what an explorer shows on the right
+      const internal = '01' + '00'.repeat(30) + 'ff';
+      const display = 'ff' + '00'.repeat(30) + '01';
+      const hash = wasmModule.Hash.from_hex_reversed(display);
+      wasmObjects.push(hash);
+      expect(hash.to_hex()).toBe(internal);
+      expect(hash.to_hex_reversed()).toBe(display);
+      expect(hash.to_bytes_reversed()[0]).toBe(0xff);
+
+      const fromBytes = wasmModule.Hash.from_bytes_reversed(hash.to_bytes_reversed());
+      wasmObjects.push(fromBytes);
+      expect(fromBytes.to_hex()).toBe(internal);
+    });
+
     it('should create and manage WasmStump objects', () => {
       const stump = new wasmModule.WasmStump();
       wasmObjects.push(stump);
@@ -75,6 +123,62 @@ describe('Rustreexo WASM Node.js Tests', () => {
       expect(reconstructed.num_leaves()).toBe(stump.num_leaves());
     });
 
+    it('should build a stump from header roots and check the root count', () => {
+      const source = new wasmModule.WasmStump();
+      wasmObjects.push(source);
+
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      source.modify(JSON.stringify({ targets: [], hashes: [] }), leaves, []);
+
+      const stump = wasmModule.WasmStump.from_header_roots(source.roots(), 6n);
+      wasmObjects.push(stump);
+      expect(stump.roots()).toEqual(source.roots());
+      expect(stump.num_leaves()).toBe(6n);
+
+      let error: any;
+      try {
+        wasmModule.WasmStump.from_header_roots(source.roots(), 7n);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.InconsistentRoots);
+    });
+
+    it('should tell whether a hash is one of the stump roots', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+
+      const leaves = Array.from({ length: 3 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.add(leaves);
+
+      stump.roots().forEach((root: string) => expect(stump.contains_root(root)).toBe(true));
+      expect(stump.contains_root(leaves[2])).toBe(true);
+      expect(stump.contains_root(leaves[0])).toBe(false);
+      expect(() => stump.contains_root('xyz')).toThrow();
+    });
+
+    it('should build a stump from a pull source', () => {
+      const leaves = Array.from({ length: 3000 }, (_, i) => (i + 1).toString(16).padStart(64, '0'));
+      const expected = new wasmModule.WasmStump();
+      wasmObjects.push(expected);
+      expected.add(leaves);
+
+      let next = 0;
+      const stump = wasmModule.WasmStump.build_from_source(() => (next < leaves.length ? leaves[next++] : null));
+      wasmObjects.push(stump);
+      expect(stump.num_leaves()).toBe(3000n);
+      expect(stump.roots()).toEqual(expected.roots());
+
+      let calls = 0;
+      expect(() => wasmModule.WasmStump.build_from_source(() => {
+        if (++calls > 5) {
+          throw new Error('disk read failed');
+        }
+        return leaves[calls];
+      })).toThrow(/disk read failed/);
+      expect(() => wasmModule.WasmStump.build_from_source(() => 42)).toThrow();
+    });
+
     it('should create and manage WasmPollard objects', () => {
       const pollard = new wasmModule.WasmPollard();
       wasmObjects.push(pollard);
@@ -129,6 +233,26 @@ describe('Rustreexo WASM Node.js Tests', () => {
       expect(/^[0-9a-f]+$/.test(parent.to_hex())).toBe(true);
     });
 
+    it('should build deterministic hashes from counters', () => {
+      const one = wasmModule.Hash.from_u64(1n);
+      const big = wasmModule.Hash.from_u64(0x0102030405060708n);
+      wasmObjects.push(one, big);
+
+      expect(one.to_hex()).toBe('01' + '0'.repeat(62));
+      expect(big.to_hex()).toBe('0807060504030201' + '0'.repeat(48));
+    });
+
+    it('should abbreviate hashes for display', () => {
+      const hexString = 'a1b2c3d4e5f67890123456789012345678901234567890123456789012f9e0';
+      const hash = new wasmModule.Hash(hexString);
+      wasmObjects.push(hash);
+
+      expect(hash.to_short(4, 4)).toBe('a1b2…f9e0');
+      expect(hash.to_short(0, 4)).toBe('…f9e0');
+      expect(hash.to_short(40, 40)).toBe(hexString);
+      expect(hash.to_short(100, 0)).toBe(hexString);
+    });
+
     it('should reject invalid hash inputs', () => {
       const invalidInputs = [
         'invalid_hex',
@@ -141,6 +265,23 @@ describe('Rustreexo WASM Node.js Tests', () => {
         expect(() => new wasmModule.Hash(input)).toThrow();
       });
     });
+
+    it('should switch hex output to uppercase on request', () => {
+      const hex = 'deadbeefcafebabe0123456789abcdef0123456789abcdef0123456789abcdef';
+      const hash = new wasmModule.Hash(hex);
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(hash, stump);
+      stump.modify(JSON.stringify({ targets: [], hashes: [] }), [hex], []);
+
+      try {
+        wasmModule.set_hex_uppercase(true);
+        expect(hash.to_hex()).toBe(hex.toUpperCase());
+        expect(stump.roots()).toEqual([hex.toUpperCase()]);
+      } finally {
+        wasmModule.set_hex_uppercase(false);
+      }
+      expect(hash.to_hex()).toBe(hex);
+    });
   });
 
   describe('Proof Operations', () => {
@@ -191,101 +332,2478 @@ describe('Rustreexo WASM Node.js Tests', () => {
         pollard.prove_single(nonExistentHash);
       }).toThrow(/Leaf not found/);
     });
-  });
 
-  describe('Error Handling', () => {
-    it('should handle invalid JSON in stump operations', () => {
-      expect(() => {
-        wasmModule.WasmStump.from_json('invalid json');
-      }).toThrow();
-      
-      expect(() => {
-        wasmModule.WasmStump.from_json('{}'); // Missing required fields
-      }).toThrow();
+    it('should prove each leaf independently', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const proofs = pollard.prove_each([leaves[3], leaves[1]]);
+      expect(proofs).toHaveLength(2);
+      expect(proofs[0]).toBe(pollard.prove_single(leaves[3]));
+      expect(proofs[1]).toBe(pollard.prove_single(leaves[1]));
+
+      const missing = 'f'.repeat(64);
+      expect(() => pollard.prove_each([leaves[0], missing])).toThrow(new RegExp(missing));
     });
 
-    it('should handle invalid proof formats', () => {
+    it('should return the roots a proof reconstructs', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.batch_proof([leaves[2], leaves[5]]);
+      expect(stump.verify_and_roots(proof, [leaves[2], leaves[5]])).toEqual(stump.roots());
+      expect(() => stump.verify_and_roots(proof, [leaves[5], leaves[2]])).toThrow();
+    });
+
+    it('should expose proof targets and hashes for independent reconstruction', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const proof = pollard.prove_single(leaves[0]);
+      const targets = wasmModule.proof_targets(proof);
+      const hashes = wasmModule.proof_hashes(proof);
+      expect(Array.from(targets)).toEqual([0n]);
+      expect(hashes[0]).toBe(leaves[1]);
+
+      const [leaf, sibling, uncle] = [leaves[0], ...hashes].map((hex: string) => new wasmModule.Hash(hex));
+      const node = wasmModule.Hash.parent_hash(leaf, sibling);
+      const root = wasmModule.Hash.parent_hash(node, uncle);
+      wasmObjects.push(leaf, sibling, uncle, node, root);
+      expect(root.to_hex()).toBe(pollard.roots()[0]);
+    });
+
+    it('should keep targets and hashes together in a proof bundle', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const bundle = pollard.prove_bundle([leaves[5], leaves[2]]);
+      wasmObjects.push(bundle);
+      expect(Array.from(bundle.targets)).toEqual([5n, 2n]);
+      expect(bundle.hashes).toEqual([leaves[5], leaves[2]]);
+      expect(stump.verify_bundle(bundle)).toBe(true);
+      expect(pollard.verify_bundle(bundle)).toBe(true);
+      expect(() => new wasmModule.WasmProofBundle(bundle.proof, [leaves[5]])).toThrow();
+
+      stump.modify_bundle(bundle, []);
+      pollard.modify_bundle(bundle, JSON.stringify([]));
+      expect(stump.roots()).toEqual(pollard.roots().reverse());
+    });
+
+    it('should apply reproducible random ops', () => {
+      const start = new wasmModule.WasmStump();
+      start.add(['ab'.repeat(32), 'cd'.repeat(32), 'ef'.repeat(32)]);
+      const a = wasmModule.WasmStump.from_json(start.to_json());
+      const b = wasmModule.WasmStump.from_json(start.to_json());
+      wasmObjects.push(start, a, b);
+
+      const log = a.apply_random_ops(42n, 50);
+      expect(log).toHaveLength(50);
+      expect(log.some((op: any) => op.del.length > 0)).toBe(true);
+      expect(b.apply_random_ops(42n, 50)).toEqual(log);
+      expect(b.roots()).toEqual(a.roots());
+      expect(start.is_ancestor_of(a, JSON.stringify(log))).toBe(true);
+    });
+
+    it('should report node count and memory as the pollard grows and shrinks', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      expect(pollard.num_nodes()).toBe(0n);
+      const emptyBytes = pollard.memory_bytes();
+
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 4).map(hash => ({ hash, remember: true }))), []);
+      expect(pollard.num_nodes()).toBe(7n);
+      const halfBytes = pollard.memory_bytes();
+      expect(halfBytes).toBeGreaterThan(emptyBytes);
+
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(4).map(hash => ({ hash, remember: true }))), []);
+      expect(pollard.num_nodes()).toBe(15n);
+      const fullBytes = pollard.memory_bytes();
+      expect(fullBytes).toBeGreaterThan(halfBytes);
+
+      pollard.prune(leaves.slice(4));
+      expect(pollard.num_nodes()).toBeLessThan(15n);
+      expect(pollard.memory_bytes()).toBeLessThan(fullBytes);
+    });
+
+    it('should prune remembered leaves without breaking other proofs', () => {
       const stump = new wasmModule.WasmStump();
       const pollard = new wasmModule.WasmPollard();
       wasmObjects.push(stump, pollard);
-      
-      const invalidProofs = [
-        'invalid json',
-        '{}', // Missing required fields
-        '{"targets": []}', // Missing other fields
-        '{"proof": []}' // Missing other fields
-      ];
-      
-      invalidProofs.forEach(invalidProof => {
-        expect(() => {
-          stump.verify(invalidProof, []);
-        }).toThrow();
-        
-        expect(() => {
-          pollard.verify(invalidProof, []);
-        }).toThrow();
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves.slice(0, 7), []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 7).map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, [leaves[7]], []);
+      pollard.add_leaf_with_data(leaves[7], true, new Uint8Array([7]));
+      const roots = pollard.roots();
+      const size = pollard.serialize().length;
+
+      const pruned = [leaves[2], leaves[3], leaves[5], leaves[7]];
+      pollard.prune(pruned);
+      expect(pollard.roots()).toEqual(roots);
+      expect(pollard.remembered_count()).toBe(4);
+      expect(pollard.serialize().length).toBeLessThan(size);
+      expect(pollard.leaf_data(leaves[7])).toBeUndefined();
+      for (const hash of pruned) {
+        expect(() => pollard.prove_single(hash)).toThrow();
+      }
+      for (const hash of [leaves[0], leaves[1], leaves[4], leaves[6]]) {
+        const proof = pollard.prove_single(hash);
+        expect(stump.verify(proof, [hash])).toBe(true);
+        expect(pollard.verify(proof, [hash])).toBe(true);
+      }
+
+      expect(() => pollard.prune([leaves[0], leaves[2]])).toThrow(new RegExp(leaves[2]));
+      expect(pollard.remembered_count()).toBe(4);
+    });
+
+    it('should prove many leaves in one batch matching single proofs', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const wanted = [leaves[9], leaves[2], leaves[5], leaves[3]];
+      const { proof, positions } = pollard.prove_many(wanted);
+      expect(positions).toBeInstanceOf(Float64Array);
+      expect(Array.from(positions)).toEqual(JSON.parse(proof).targets);
+      wanted.forEach((hash, i) => {
+        const single = pollard.prove_single(hash);
+        expect(JSON.parse(single).targets).toEqual([positions[i]]);
+        expect(stump.verify(single, [hash])).toBe(true);
       });
+      expect(stump.verify(proof, wanted)).toBe(true);
+      expect(proof).toBe(pollard.batch_proof(wanted));
+
+      expect(() => pollard.prove_many([leaves[0], '9'.repeat(64)])).toThrow();
     });
-  });
 
-  describe('Memory Management', () => {
-    it('should handle multiple object creation and cleanup', () => {
-      const objects = [];
-      
-      // Create many objects
-      for (let i = 0; i < 50; i++) {
-        const stump = new wasmModule.WasmStump();
-        const pollard = new wasmModule.WasmPollard();
-        objects.push(stump, pollard);
-        
-        expect(stump.num_leaves()).toBe(0n);
-        expect(pollard.num_leaves()).toBe(0n);
+    it('should prove and verify a single leaf in binary', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 5 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      const bytesOf = (hash: string) => Uint8Array.from(Buffer.from(hash, 'hex'));
+
+      const proof = pollard.prove_single_bytes(bytesOf(leaves[2]));
+      expect(proof).toBeInstanceOf(Uint8Array);
+      expect(wasmModule.proof_from_bytes(proof)).toBe(pollard.prove_single(leaves[2]));
+      expect(stump.verify_single(proof, bytesOf(leaves[2]))).toBe(true);
+      expect(stump.verify_single(proof, bytesOf(leaves[3]))).toBe(false);
+
+      expect(() => pollard.prove_single_bytes(bytesOf('9'.repeat(64)))).toThrow();
+    });
+
+    it('should explain why a proof fails to verify', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const proof = pollard.prove_single(leaves[1]);
+      stump.check_proof(proof, [leaves[1]]);
+      pollard.check_proof(proof, [leaves[1]]);
+
+      const failure = (fn: () => void) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e;
+        }
+        throw new Error('expected a failure');
+      };
+
+      const parsed = JSON.parse(proof);
+      const padded = JSON.stringify({ ...parsed, hashes: [...parsed.hashes, ...parsed.hashes, ...parsed.hashes] });
+      const malformed = failure(() => stump.check_proof(padded, [leaves[1]]));
+      expect(malformed.kind).toBe(wasmModule.UtreexoErrorKind.MalformedProof);
+
+      const delProof = pollard.prove_single(leaves[3]);
+      stump.modify(delProof, [], [leaves[3]]);
+      pollard.modify(delProof, '[]', [leaves[3]]);
+      const stale = failure(() => stump.check_proof(proof, [leaves[1]]));
+      expect(stale.kind).toBe(wasmModule.UtreexoErrorKind.ProofVerificationFailed);
+      expect(failure(() => pollard.check_proof(proof, [leaves[1]])).kind).toBe(stale.kind);
+      expect(stale.message).not.toBe(malformed.message);
+      expect(pollard.verify(proof, [leaves[1]])).toBe(false);
+    });
+
+    it('should tag errors with a kind for each failure path', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          expect(typeof e.message).toBe('string');
+          return e.kind;
+        }
+        throw new Error('expected a failure');
+      };
+      const Kind = wasmModule.UtreexoErrorKind;
+
+      expect(kindOf(() => new wasmModule.Hash('xyz'))).toBe(Kind.InvalidHash);
+      expect(kindOf(() => stump.modify(emptyProof, ['zz'], []))).toBe(Kind.InvalidHash);
+      expect(kindOf(() => pollard.modify(emptyProof, JSON.stringify([{ hash: 'zz' }]), []))).toBe(Kind.InvalidHash);
+      expect(kindOf(() => stump.verify('{"targets":', [leaves[0]]))).toBe(Kind.InvalidJson);
+      expect(kindOf(() => pollard.modify(emptyProof, 'not json', []))).toBe(Kind.InvalidJson);
+
+      const wrongProof = pollard.prove_single(leaves[1]);
+      expect(kindOf(() => stump.modify(wrongProof, [], [leaves[0]]))).toBe(Kind.ProofVerificationFailed);
+      expect(() => stump.modify(wrongProof, [], [leaves[0]])).toThrow(/^Failed to modify stump: proof does not verify/);
+      expect(kindOf(() => stump.modify(wrongProof, [], []))).toBe(Kind.DeletionNotInProof);
+    });
+
+    it('should apply a modify to a new stump', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 3 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves.slice(0, 2), []);
+      const roots = stump.roots();
+
+      const next = stump.apply(emptyProof, leaves.slice(2), []);
+      wasmObjects.push(next);
+      expect(stump.num_leaves()).toBe(2n);
+      expect(stump.roots()).toEqual(roots);
+      expect(next.num_leaves()).toBe(3n);
+      expect(next.sequence()).toBe(stump.sequence() + 1n);
+
+      const expected = new wasmModule.WasmStump();
+      wasmObjects.push(expected);
+      expected.modify(emptyProof, leaves, []);
+      expect(next.roots()).toEqual(expected.roots());
+    });
+
+    it('should clone accumulators independently', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves.slice(0, 4), []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 4).map(hash => ({ hash, remember: true }))), []);
+
+      const stumpCopy = stump.clone();
+      const pollardCopy = pollard.clone();
+      wasmObjects.push(stumpCopy, pollardCopy);
+      const roots = stump.roots();
+      const pollardRoots = pollard.roots();
+
+      const delProof = pollard.prove_single(leaves[0]);
+      stumpCopy.modify(delProof, leaves.slice(4), [leaves[0]]);
+      pollardCopy.modify(delProof, JSON.stringify(leaves.slice(4).map(hash => ({ hash, remember: true }))), [leaves[0]]);
+      expect(stumpCopy.roots()).toEqual(pollardCopy.roots_biggest_first());
+
+      expect(stump.num_leaves()).toBe(4n);
+      expect(stump.roots()).toEqual(roots);
+      expect(pollard.num_leaves()).toBe(4n);
+      expect(pollard.roots()).toEqual(pollardRoots);
+      expect(pollard.verify(pollard.prove_single(leaves[0]), [leaves[0]])).toBe(true);
+    });
+
+    it('should hash leaf data with the utreexo tag', () => {
+      const data = Uint8Array.from({ length: 16 }, (_, i) => i);
+      const leaf = wasmModule.Hash.from_leaf_data(data);
+      wasmObjects.push(leaf);
+
+      const tag = crypto.createHash('sha512').update('UtreexoV1').digest();
+      const expected = crypto.createHash('sha512-256').update(tag).update(tag).update(data).digest('hex');
+      expect(leaf.to_hex()).toBe(expected);
+      expect(leaf.to_hex()).toBe('202916249f7b53a90138362826cbc5cc53fb13d233d26f3c3ecda0883236445c');
+    });
+
+    it('should expose the empty node hash', () => {
+      const empty = wasmModule.Hash.empty();
+      const zeros = new wasmModule.Hash('00'.repeat(32));
+      const real = wasmModule.Hash.from_u64(1n);
+      wasmObjects.push(empty, zeros, real);
+
+      expect(empty.is_empty()).toBe(true);
+      expect(zeros.is_empty()).toBe(true);
+      expect(real.is_empty()).toBe(false);
+      expect(empty.equals(zeros)).toBe(true);
+      expect(empty.to_bytes()).toEqual(new Uint8Array(32));
+    });
+
+    it('should compare hashes by their bytes', () => {
+      const low = wasmModule.Hash.from_u64(1n);
+      const high = wasmModule.Hash.from_u64(2n);
+      const same = new wasmModule.Hash(low.to_hex());
+      wasmObjects.push(low, high, same);
+
+      expect(low.equals(same)).toBe(true);
+      expect(low.equals(high)).toBe(false);
+      expect(low.compare(same)).toBe(0);
+      expect(low.compare(high)).toBe(-1);
+      expect(high.compare(low)).toBe(1);
+      expect(low.hash_code()).toBe(same.hash_code());
+      expect(low.hash_code()).not.toBe(high.hash_code());
+    });
+
+    it('should match the hex methods with packed hash bytes', () => {
+      const hexPollard = new wasmModule.WasmPollard();
+      const rawPollard = new wasmModule.WasmPollard();
+      const hexStump = new wasmModule.WasmStump();
+      const rawStump = new wasmModule.WasmStump();
+      wasmObjects.push(hexPollard, rawPollard, hexStump, rawStump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const pack = (hashes: string[]) => Uint8Array.from(Buffer.from(hashes.join(''), 'hex'));
+
+      hexPollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      rawPollard.modify_raw(emptyProof, pack(leaves), wasmModule.pack_remember_flags(leaves.map(() => true)), new Uint8Array());
+      hexStump.modify(emptyProof, leaves, []);
+      rawStump.modify_raw(emptyProof, pack(leaves), new Uint8Array());
+
+      expect(rawPollard.roots_raw()).toEqual(pack(hexPollard.roots()));
+      expect(rawStump.roots_raw()).toEqual(pack(hexStump.roots()));
+
+      const proof = rawPollard.batch_proof_raw(pack([leaves[1], leaves[4]]));
+      expect(proof).toBe(hexPollard.batch_proof([leaves[1], leaves[4]]));
+      expect(rawPollard.verify_raw(proof, pack([leaves[1], leaves[4]]))).toBe(true);
+      expect(rawStump.verify_raw(proof, pack([leaves[1], leaves[4]]))).toBe(
+        hexStump.verify(proof, [leaves[1], leaves[4]]),
+      );
+
+      const delProof = hexPollard.prove_single(leaves[5]);
+      hexStump.modify(delProof, [], [leaves[5]]);
+      rawStump.modify_raw(delProof, new Uint8Array(), pack([leaves[5]]));
+      expect(rawStump.roots()).toEqual(hexStump.roots());
+
+      let error: any;
+      try {
+        rawStump.verify_raw(proof, new Uint8Array(33));
+      } catch (e) {
+        error = e;
       }
-      
-      expect(objects).toHaveLength(100);
-      
-      // Clean up
-      objects.forEach(obj => obj.free());
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.InvalidHash);
     });
 
-    it('should handle repeated creation/destruction cycles', () => {
-      for (let cycle = 0; cycle < 10; cycle++) {
-        const stump = new wasmModule.WasmStump();
-        const pollard = new wasmModule.WasmPollard();
-        
-        expect(stump.num_leaves()).toBe(0n);
-        expect(pollard.num_leaves()).toBe(0n);
-        
-        stump.free();
-        pollard.free();
+    it('should round trip a pollard through serialize', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 9 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const additions = leaves.map(hash => ({ hash, remember: true }));
+      pollard.modify(emptyProof, JSON.stringify(additions.slice(0, 4)), []);
+      pollard.modify(emptyProof, JSON.stringify(additions.slice(4)), []);
+      pollard.modify(pollard.prove_single(leaves[8]), '[]', [leaves[8]]);
+
+      const restored = wasmModule.WasmPollard.deserialize(pollard.serialize());
+      wasmObjects.push(restored);
+      expect(restored.roots()).toEqual(pollard.roots());
+      expect(restored.num_leaves()).toBe(pollard.num_leaves());
+      expect(restored.serialize()).toEqual(pollard.serialize());
+
+      for (const leaf of leaves.slice(0, 8)) {
+        const proof = restored.prove_single(leaf);
+        expect(pollard.verify(proof, [leaf])).toBe(true);
       }
     });
-  });
 
-  describe('Accumulator Modifications', () => {
-    it('should handle empty modifications without errors', () => {
+    it('should refuse hostile pollard blobs as corrupt', () => {
+      // Envelope, BE leaf count, one root marker with the given preorder nodes, no
+      // remembered leaves
+      const blob = (leaves: bigint, row: number, nodes: number[][]) => {
+        const count = new Uint8Array(8);
+        new DataView(count.buffer).setBigUint64(0, leaves);
+        const markers = Array.from({ length: 64 }, (_, r) => (r === row ? [1, ...nodes.flat()] : [0]));
+        return Uint8Array.from([2, 5, ...count, ...markers.flat(), ...new Array(8).fill(0)]);
+      };
+      const node = (isLeaf: number) => [isLeaf, 2, ...new Array(32).fill(0xab)];
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+
+      const corrupt = wasmModule.UtreexoErrorKind.CorruptState;
+      // A root marker on a row the leaf count has no tree on
+      expect(kindOf(() => wasmModule.WasmPollard.deserialize(blob(1n, 5, [node(1)])))).toBe(corrupt);
+      expect(kindOf(() => wasmModule.WasmPollard.deserialize(blob(1n, 63, [node(1)])))).toBe(corrupt);
+      // More leaves than the position math supports
+      expect(kindOf(() => wasmModule.WasmPollard.deserialize(blob(1n << 63n, 63, [node(1)])))).toBe(corrupt);
+      // Branches below the bottom row, which would otherwise recurse until the bytes
+      // run out
+      expect(kindOf(() => wasmModule.WasmPollard.deserialize(blob(1n, 0, [node(0), node(1), node(1)])))).toBe(corrupt);
+      const deep = Array.from({ length: 4096 }, () => node(0));
+      expect(kindOf(() => wasmModule.WasmPollard.deserialize(blob(4n, 2, deep)))).toBe(corrupt);
+
+      // The same layout with a sound tree still loads
+      const fine = wasmModule.WasmPollard.deserialize(blob(2n, 1, [node(0), node(1), node(1)]));
+      wasmObjects.push(fine);
+      expect(fine.num_leaves()).toBe(2n);
+    });
+
+    it('should serialize stumps to compact binary', () => {
+      const empty = new wasmModule.WasmStump();
+      wasmObjects.push(empty);
+      // Version and type tag, then zero leaves, zero roots and a zero sequence
+      expect(empty.serialize()).toEqual(new Uint8Array([2, 1, ...new Array(24).fill(0)]));
+
       const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      const leaves = Array.from({ length: 5 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(JSON.stringify({ targets: [], hashes: [] }), leaves, []);
+
+      const bytes = stump.serialize();
+      const restored = wasmModule.WasmStump.deserialize(bytes);
+      wasmObjects.push(restored);
+      expect(restored.serialize()).toEqual(bytes);
+      expect(restored.roots()).toEqual(stump.roots());
+      expect(restored.sequence()).toBe(stump.sequence());
+      expect(bytes.length).toBeLessThan(new TextEncoder().encode(stump.to_json()).length / 2);
+    });
+
+    it('should expose proof targets and reject unsorted ones', () => {
+      const proof = wasmModule.WasmProof.from_json(JSON.stringify({ targets: [1, 4, 6], hashes: [] }));
+      wasmObjects.push(proof);
+      expect(proof.n_targets).toBe(3);
+      expect(proof.sorted_targets()).toEqual(new BigUint64Array([1n, 4n, 6n]));
+
+      for (const targets of [[4, 1], [2, 2]]) {
+        const bad = wasmModule.WasmProof.from_json(JSON.stringify({ targets, hashes: [] }));
+        wasmObjects.push(bad);
+        expect(bad.targets).toEqual(new BigUint64Array(targets.map(BigInt)));
+
+        let error: any;
+        try {
+          bad.sorted_targets();
+        } catch (e) {
+          error = e;
+        }
+        expect(error.kind).toBe(wasmModule.UtreexoErrorKind.MalformedProof);
+      }
+    });
+
+    it('should pass proofs around as WasmProof objects', () => {
       const pollard = new wasmModule.WasmPollard();
-      wasmObjects.push(stump, pollard);
-      
-      const emptyProof = JSON.stringify({
-        targets: [],
-        proof: [],
-        hashes: []
-      });
-      
-      // These operations should not change the state
-      const initialStumpLeaves = stump.num_leaves();
-      const initialPollardLeaves = pollard.num_leaves();
-      
-      // For stump: modify(proof_json, add_hashes, del_hashes)
-      stump.modify(emptyProof, [], []);
-      
-      // For pollard: modify(proof_json, additions_json, del_hashes)
-      pollard.modify(emptyProof, JSON.stringify([]), []);
-      
-      expect(stump.num_leaves()).toBe(initialStumpLeaves);
-      expect(pollard.num_leaves()).toBe(initialPollardLeaves);
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 5 }, (_, i) => (i + 1).toString(16).repeat(64));
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.prove([leaves[0], leaves[3]]);
+      wasmObjects.push(proof);
+      expect(proof.targets).toEqual(new BigUint64Array([0n, 3n]));
+      expect(proof.to_json()).toBe(pollard.batch_proof([leaves[0], leaves[3]]));
+      expect(stump.verify_proof(proof, [leaves[0], leaves[3]])).toBe(true);
+      expect(pollard.verify_proof(proof, [leaves[0], leaves[3]])).toBe(true);
+
+      const parsed = wasmModule.WasmProof.from_json(proof.to_json());
+      wasmObjects.push(parsed);
+      expect(parsed.hashes).toEqual(proof.hashes);
+      expect(stump.verify(parsed.to_json(), [leaves[0], leaves[3]])).toBe(true);
+
+      let error: any;
+      try {
+        wasmModule.WasmProof.from_json('not json');
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.InvalidJson);
+    });
+
+    it('should refresh an old proof from a stump update', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 6).map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves.slice(0, 6), []);
+
+      const oldProof = pollard.prove_single(leaves[4]);
+      const delProof = pollard.prove_single(leaves[1]);
+      const update = wasmModule.WasmUpdate.from_json(
+        stump.modify_with_update(delProof, leaves.slice(6), [leaves[1]]).to_json(),
+      );
+      wasmObjects.push(update);
+      expect(update.added_positions).toEqual(new BigUint64Array([6n, 7n]));
+      expect(update.deleted_positions).toEqual(new BigUint64Array([1n]));
+
+      let stale: any;
+      try {
+        stale = stump.verify(oldProof, [leaves[4]]);
+      } catch (e) {
+        stale = false;
+      }
+      expect(stale).toBe(false);
+
+      const refreshed = update.update_proof(oldProof, [leaves[4]]);
+      wasmObjects.push(refreshed);
+      expect(refreshed.hashes).toEqual([leaves[4]]);
+      expect(stump.verify(refreshed.proof, [leaves[4]])).toBe(true);
+      expect(wasmModule.is_proof_fresh(refreshed, stump)).toBe(true);
+
+      let deletedError: any;
+      try {
+        update.update_proof(delProof, [leaves[1]]);
+      } catch (e) {
+        deletedError = e;
+      }
+      expect(deletedError.kind).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+    });
+
+    it('should compute a stable checkpoint digest', () => {
+      const stump = new wasmModule.WasmStump();
+      const copy = new wasmModule.WasmStump();
+      const empty = new wasmModule.WasmStump();
+      const otherEmpty = new wasmModule.WasmStump();
+      wasmObjects.push(stump, copy, empty, otherEmpty);
+      const leaves = ['01', '02', '03'].map((b) => b.repeat(32));
+      stump.add(leaves);
+      copy.add(leaves);
+
+      const digest = stump.checkpoint_digest();
+      const copyDigest = copy.checkpoint_digest();
+      const state = stump.state_hash();
+      const emptyDigest = empty.checkpoint_digest();
+      const otherEmptyDigest = otherEmpty.checkpoint_digest();
+      wasmObjects.push(digest, copyDigest, state, emptyDigest, otherEmptyDigest);
+      expect(digest.to_hex()).toBe(copyDigest.to_hex());
+      expect(digest.to_hex()).not.toBe(state.to_hex());
+      expect(emptyDigest.to_hex()).toBe(otherEmptyDigest.to_hex());
+      // Pinned: the preimage must never change
+      expect(emptyDigest.to_hex()).toBe('d697f576d0ab0661961917464efaf1dfa99963ffc97005a4b49feb7d1db41cf1');
+      expect(emptyDigest.to_hex()).not.toBe(digest.to_hex());
+    });
+
+    it('should list the positions a proof can prove', () => {
+      const proof = JSON.stringify({ targets: [6, 1, 4, 1], hashes: [] });
+      expect(Array.from(wasmModule.proof_provable_positions(proof))).toEqual([1n, 4n, 6n]);
+      expect(Array.from(wasmModule.proof_targets(proof))).toEqual([6n, 1n, 4n, 1n]);
+      expect(Array.from(wasmModule.proof_provable_positions(JSON.stringify({ targets: [], hashes: [] })))).toEqual([]);
+    });
+
+    it('should add leaves with packed remember flags', () => {
+      const flags = [true, false, false, true, false, false, false, false, true, true];
+      const packed = wasmModule.pack_remember_flags(flags);
+      expect(Array.from(packed)).toEqual([0b1001, 0b11]);
+      expect(wasmModule.unpack_remember_flags(packed, flags.length)).toEqual(flags);
+      expect(() => wasmModule.unpack_remember_flags(packed, 9)).toThrow(/Bits past/);
+
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = flags.map((_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      pollard.add_with_flags(leaves, packed);
+      expect(pollard.num_leaves()).toBe(10n);
+      const remembered = leaves.filter((_, i) => flags[i]);
+      expect(pollard.missing_proof_targets(remembered)).toEqual([]);
+      expect(pollard.missing_proof_targets([leaves[5], leaves[6]])).toEqual([leaves[5], leaves[6]]);
+    });
+
+    it('should verify proofs with hashes streamed in', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const targets = [leaves[2], leaves[5]];
+      const proof = pollard.batch_proof(targets);
+      const verifier = new wasmModule.StreamingVerifier(stump, proof);
+      wasmObjects.push(verifier);
+      expect(verifier.remaining).toBe(2);
+
+      verifier.push_hash(targets[0]);
+      let error: any;
+      try {
+        verifier.finish();
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+
+      verifier.push_hash(targets[1]);
+      expect(verifier.remaining).toBe(0);
+      expect(verifier.finish()).toBe(true);
+      expect(() => verifier.push_hash(leaves[0])).toThrow(/targets/);
+
+      const wrong = new wasmModule.StreamingVerifier(stump, proof);
+      wasmObjects.push(wrong);
+      wrong.push_hash(targets[1]);
+      wrong.push_hash(targets[0]);
+      expect(wrong.finish()).toBe(false);
+    });
+
+    it('should compare roots ignoring empty slots', () => {
+      const a = 'aa'.repeat(32);
+      const b = 'bb'.repeat(32);
+      const zeros = '00'.repeat(32);
+      expect(wasmModule.roots_equivalent([a, 'empty', b], [a, b])).toBe(true);
+      expect(wasmModule.roots_equivalent([a, zeros, b, zeros], ['empty', a, b])).toBe(true);
+      expect(wasmModule.roots_equivalent([a, b], [b, a])).toBe(false);
+      expect(wasmModule.roots_equivalent([a], [a, b])).toBe(false);
+      expect(() => wasmModule.roots_equivalent(['zz'], [a])).toThrow(/Invalid root hash/);
+    });
+
+    it('should list deletion targets the pollard cannot prove', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i < 4 }))), []);
+
+      const wanted = [leaves[1], leaves[6], leaves[3], leaves[7], 'ee'.repeat(32)];
+      expect(pollard.missing_proof_targets(wanted)).toEqual([leaves[6], leaves[7], 'ee'.repeat(32)]);
+      expect(pollard.missing_proof_targets([leaves[0], leaves[2]])).toEqual([]);
+    });
+
+    it('should advance a peer with an update proof', () => {
+      const old = new wasmModule.WasmStump();
+      old.add(['aa'.repeat(32), 'bb'.repeat(32), 'cc'.repeat(32)]);
+      const live = wasmModule.WasmStump.from_json(old.to_json());
+      const peer = wasmModule.WasmStump.from_json(old.to_json());
+      wasmObjects.push(old, live, peer);
+
+      const log = live.apply_random_ops(7n, 6);
+      for (const byte of ['01', '02', '03']) {
+        log.push({ add: [byte.repeat(32)], del: [], proof: { targets: [], hashes: [] } });
+        live.add([byte.repeat(32)]);
+      }
+      const update = wasmModule.compute_update_proof(old, JSON.stringify(log));
+      expect(JSON.parse(update).steps.length).toBeLessThan(log.length);
+
+      peer.apply_update_proof(update);
+      expect(peer.roots()).toEqual(live.roots());
+      expect(peer.num_leaves()).toBe(live.num_leaves());
+
+      let error: any;
+      try {
+        peer.apply_update_proof(update);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.RootsMismatch);
+    });
+
+    it('should count proof targets without a full parse', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const proof = pollard.batch_proof([leaves[1], leaves[4], leaves[6]]);
+      expect(wasmModule.proof_target_count(proof)).toBe(3);
+      expect(wasmModule.proof_target_count(proof)).toBe(wasmModule.proof_targets(proof).length);
+      expect(wasmModule.proof_target_count(emptyProof)).toBe(0);
+      expect(() => wasmModule.proof_target_count('{"hashes": []}')).toThrow(/targets/);
+    });
+
+    it('should verify proofs against historical roots', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const snapshot = stump.roots();
+      const proof = pollard.prove_single(leaves[2]);
+      const later = ['aa'.repeat(32), 'bb'.repeat(32), 'cc'.repeat(32)];
+      stump.modify(emptyProof, later, []);
+      expect(stump.verify(proof, [leaves[2]])).toBe(false);
+
+      expect(wasmModule.verify_historical(snapshot, 6n, proof, [leaves[2]])).toBe(true);
+      expect(wasmModule.verify_historical(snapshot, 6n, proof, [leaves[3]])).toBe(false);
+      expect(stump.num_leaves()).toBe(9n);
+
+      let error: any;
+      try {
+        wasmModule.verify_historical(snapshot, 7n, proof, [leaves[2]]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.InconsistentRoots);
+    });
+
+    it('should commit to a list of hashes with merkle_root', () => {
+      const leaves = ['01', '02', '03', '04', '05'].map((b) => b.repeat(32));
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      stump.add(leaves);
+
+      const root = wasmModule.merkle_root(leaves);
+      const state = stump.state_hash();
+      const reversed = wasmModule.merkle_root(leaves.slice().reverse());
+      const fewer = wasmModule.merkle_root(leaves.slice(0, 4));
+      wasmObjects.push(root, state, reversed, fewer);
+      expect(root.to_hex()).toBe(state.to_hex());
+      expect(reversed.to_hex()).not.toBe(root.to_hex());
+      expect(fewer.to_hex()).not.toBe(root.to_hex());
+    });
+
+    it('should serialize a pollard in chunks and load it back', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const loader = new wasmModule.PollardLoader();
+      wasmObjects.push(pollard, loader);
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i % 3 === 0 }))), []);
+      pollard.delete([leaves[0]]);
+
+      let cursor = 0n;
+      let chunks = 0;
+      for (;;) {
+        const { bytes, next_cursor, done } = pollard.serialize_chunk(cursor, 40);
+        expect(bytes.length).toBeLessThanOrEqual(40);
+        loader.push_chunk(bytes);
+        cursor = next_cursor;
+        chunks++;
+        if (done) break;
+      }
+      expect(chunks).toBeGreaterThan(1);
+      expect(loader.bytes_received()).toBe(cursor);
+
+      const restored = loader.finish();
+      wasmObjects.push(restored);
+      expect(restored.roots()).toEqual(pollard.roots());
+      expect(restored.num_leaves()).toBe(12n);
+      expect(restored.verify(restored.prove_single(leaves[9]), [leaves[9]])).toBe(true);
+    });
+
+    it('should refuse corrupt pollard chunks as they arrive', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      pollard.modify(
+        JSON.stringify({ targets: [], hashes: [] }),
+        JSON.stringify(leaves.map(hash => ({ hash, remember: true }))),
+        [],
+      );
+      const bytes = pollard.serialize();
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+      const corrupt = wasmModule.UtreexoErrorKind.CorruptState;
+
+      const padded = new wasmModule.PollardLoader();
+      wasmObjects.push(padded);
+      padded.push_chunk(bytes.slice(0, 10));
+      expect(kindOf(() => padded.push_chunk(Uint8Array.from([...bytes.slice(10), 0])))).toBe(corrupt);
+      // The loader stays failed
+      expect(kindOf(() => padded.push_chunk(new Uint8Array()))).toBe(corrupt);
+
+      const truncated = new wasmModule.PollardLoader();
+      wasmObjects.push(truncated);
+      truncated.push_chunk(bytes.slice(0, bytes.length - 1));
+      expect(kindOf(() => truncated.finish())).toBe(corrupt);
+    });
+
+    it('should attribute batch proof bytes to each target', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const targets = [leaves[0], leaves[1], leaves[5]];
+      const { proof, per_target_bytes } = pollard.batch_proof_with_sizes(targets);
+      expect(proof).toBe(pollard.batch_proof(targets));
+      expect(per_target_bytes).toHaveLength(3);
+      const total = per_target_bytes.reduce((sum: number, bytes: number) => sum + bytes, 0);
+      expect(total).toBeCloseTo(pollard.batch_proof_encoded(targets).wire.length);
+      // Siblings share their whole path, so they pay the same
+      expect(per_target_bytes[0]).toBeCloseTo(per_target_bytes[1]);
+      expect(per_target_bytes[2]).toBeGreaterThan(per_target_bytes[0]);
+
+      const single = pollard.batch_proof_with_sizes([leaves[5]]);
+      expect(single.per_target_bytes[0]).toBe(pollard.batch_proof_encoded([leaves[5]]).wire.length);
+    });
+
+    it('should merge stumps covering disjoint leaves', () => {
+      const leaves = ['01', '02', '03', '04', '05', '06'].map((b) => b.repeat(32));
+      const a = new wasmModule.WasmStump();
+      const b = new wasmModule.WasmStump();
+      const whole = new wasmModule.WasmStump();
+      wasmObjects.push(a, b, whole);
+      a.add(leaves.slice(0, 4));
+      b.add(leaves.slice(4));
+      whole.add(leaves);
+
+      expect(a.can_merge(b)).toBe(true);
+      const merged = a.merge(b);
+      wasmObjects.push(merged);
+      expect(merged.num_leaves()).toBe(6n);
+      expect(merged.roots()).toEqual(whole.roots());
+
+      // 2 leaves followed by a 4 leaf tree would straddle tree boundaries
+      expect(b.can_merge(a)).toBe(false);
+      expect(() => b.merge(a)).toThrow(/multiple/);
+    });
+
+    it('should refuse to prove absence', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify([{ hash: 'aa'.repeat(32), remember: true }]), []);
+
+      let error: any;
+      try {
+        pollard.prove_absence('bb'.repeat(32));
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.Unsupported);
+      expect(error.message).toMatch(/insertion/);
+    });
+
+    it('should order stump roots biggest tree first', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      // Seven leaves make trees of 4, 2 and 1 leaves
+      const leaves = Array.from({ length: 7 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: false }))), []);
+
+      const parent = (left: string, right: string) => {
+        const [l, r] = [new wasmModule.Hash(left), new wasmModule.Hash(right)];
+        const p = wasmModule.Hash.parent_hash(l, r);
+        wasmObjects.push(l, r, p);
+        return p.to_hex();
+      };
+      const expected = [
+        parent(parent(leaves[0], leaves[1]), parent(leaves[2], leaves[3])),
+        parent(leaves[4], leaves[5]),
+        leaves[6],
+      ];
+      expect(stump.roots()).toEqual(expected);
+      expect(pollard.roots_biggest_first()).toEqual(expected);
+      expect(pollard.roots()).toEqual([...expected].reverse());
+    });
+
+    it('should chain state commitments across blocks', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      const genesis = '00'.repeat(32);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+
+      const commits: string[] = [];
+      let prev = genesis;
+      for (const leaf of ['aa', 'bb', 'cc'].map(byte => byte.repeat(32))) {
+        stump.modify(emptyProof, [leaf], []);
+        const commit = stump.chain_commit(prev);
+        const state = stump.state_hash();
+        const prevHash = new wasmModule.Hash(prev);
+        const expected = wasmModule.Hash.parent_hash(prevHash, state);
+        wasmObjects.push(commit, state, prevHash, expected);
+        expect(commit.to_hex()).toBe(expected.to_hex());
+        prev = commit.to_hex();
+        commits.push(prev);
+      }
+      expect(new Set(commits).size).toBe(3);
+
+      // The same final state reached through another history commits differently
+      const direct = new wasmModule.WasmStump();
+      direct.modify(emptyProof, ['aa', 'bb', 'cc'].map(byte => byte.repeat(32)), []);
+      const directCommit = direct.chain_commit(genesis);
+      wasmObjects.push(direct, directCommit);
+      expect(directCommit.to_hex()).not.toBe(commits[2]);
+    });
+
+    it('should reject proofs taller than the forest', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      // Two rows, so a single target has at most two siblings
+      const proof = JSON.parse(pollard.prove_single(leaves[1]));
+      expect(proof.hashes).toHaveLength(2);
+      proof.hashes.push(proof.hashes[0]);
+
+      let error: any;
+      try {
+        stump.verify(JSON.stringify(proof), [leaves[1]]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.MalformedProof);
+    });
+
+    it('should return roots as little-endian u32 words', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      const leaves = Array.from({ length: 3 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const words = stump.roots_u32();
+      expect(words).toBeInstanceOf(Uint32Array);
+      expect(words).toHaveLength(8 * stump.roots().length);
+      const bytes = Buffer.from(stump.roots().join(''), 'hex');
+      words.forEach((word: number, i: number) => expect(word).toBe(bytes.readUInt32LE(i * 4)));
+
+      const pollardBytes = Buffer.from(pollard.roots().join(''), 'hex');
+      expect(pollard.roots_u32()[0]).toBe(pollardBytes.readUInt32LE(0));
+    });
+
+    it('should cache verification results per stump state', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      const cache = new wasmModule.VerificationCache(2);
+      wasmObjects.push(pollard, stump, cache);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.prove_single(leaves[0]);
+      expect(cache.verify_cached(stump, proof, [leaves[0]])).toBe(true);
+      expect(cache.verify_cached(stump, proof, [leaves[0]])).toBe(true);
+      expect(cache.size).toBe(1);
+      expect(cache.verify_cached(stump, proof, [leaves[1]])).toBe(false);
+
+      // A new state keys new entries, pushing out the least recently used one
+      pollard.modify(emptyProof, JSON.stringify([{ hash: 'aa'.repeat(32), remember: true }]), []);
+      stump.modify(emptyProof, ['aa'.repeat(32)], []);
+      expect(cache.verify_cached(stump, pollard.prove_single(leaves[0]), [leaves[0]])).toBe(true);
+      expect(cache.size).toBe(2);
+      expect(cache.capacity).toBe(2);
+    });
+
+    it('should undo the last update', () => {
+      const source = new wasmModule.WasmStump();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(source, stump);
+
+      const log = source.apply_random_ops(13n, 6);
+      const updates = log.map((op: any) => {
+        const update = stump.modify_with_update(JSON.stringify(op.proof), op.add, op.del);
+        wasmObjects.push(update);
+        return update;
+      });
+      expect(stump.roots()).toEqual(source.roots());
+
+      let error: any;
+      try {
+        stump.undo(updates[2]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.RootsMismatch);
+
+      stump.undo(updates[5]);
+      stump.undo(updates[4]);
+      expect(stump.num_leaves()).toBe(updates[4].prev_num_leaves);
+    });
+
+    it('should restore the sequence on undo', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, ['aa'.repeat(32)], []);
+      const before = stump.sequence();
+      const json = stump.to_json();
+
+      const update = stump.modify_with_update(emptyProof, ['bb'.repeat(32)], []);
+      wasmObjects.push(update);
+      expect(stump.sequence()).toBe(before + 1n);
+      stump.undo(update);
+      expect(stump.sequence()).toBe(before);
+      expect(stump.to_json()).toBe(json);
+    });
+
+    it('should start a pollard from a stump', () => {
+      const stump = new wasmModule.WasmStump();
+      stump.apply_random_ops(5n, 20);
+      const pollard = wasmModule.WasmPollard.from_stump(stump);
+      wasmObjects.push(stump, pollard);
+      expect(pollard.num_leaves()).toBe(stump.num_leaves());
+      expect(pollard.roots()).toEqual(stump.roots().reverse());
+
+      const leaves = ['aa', 'bb', 'cc'].map(byte => byte.repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+      expect(pollard.roots()).toEqual(stump.roots().reverse());
+      expect(stump.verify(pollard.prove_single(leaves[1]), [leaves[1]])).toBe(true);
+    });
+
+    it('should return the positions of deleted leaves', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.batch_proof([leaves[6], leaves[1]]);
+      const positions = stump.modify_with_deletions(proof, [], [leaves[6], leaves[1]]);
+      expect(Array.from(positions)).toEqual([6n, 1n]);
+      expect(Array.from(pollard.modify_with_deletions(proof, '[]', [leaves[6], leaves[1]]))).toEqual([6n, 1n]);
+      expect(pollard.roots()).toEqual(stump.roots().reverse());
+
+      expect(Array.from(pollard.delete([leaves[4]]))).toEqual([4n]);
+    });
+
+    it('should self-check loaded state', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      pollard.delete([leaves[2]]);
+      pollard.self_check();
+      stump.modify(emptyProof, leaves, []);
+      stump.self_check();
+
+      // Six leaves make two trees, four leaves only one
+      const record = JSON.parse(stump.to_json());
+      record.leaves = 4;
+      const corrupt = wasmModule.WasmStump.from_json(JSON.stringify(record));
+      wasmObjects.push(corrupt);
+      let error: any;
+      try {
+        corrupt.self_check();
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.InconsistentRoots);
+    });
+
+    it('should return a proof as JSON and wire bytes at once', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const targets = [leaves[2], leaves[5]];
+      const { json, wire } = pollard.batch_proof_encoded(targets);
+      expect(json).toBe(pollard.batch_proof(targets));
+      expect(wire).toBeInstanceOf(Uint8Array);
+      // The versioned binary format is a version byte and type tag before the wire form
+      expect(Array.from(wasmModule.proof_to_bytes(json).subarray(2))).toEqual(Array.from(wire));
+
+      const hashBytes = targets.map(hex => new Uint8Array(Buffer.from(hex, 'hex')));
+      expect(stump.verify_wire(wire, hashBytes)).toBe(true);
+      expect(stump.verify(json, targets)).toBe(true);
+    });
+
+    it('should refuse custom empty hashes', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      stump.configure_empty_hash('00'.repeat(32));
+      pollard.configure_empty_hash('00'.repeat(32));
+      for (const target of [stump, pollard]) {
+        let error: any;
+        try {
+          target.configure_empty_hash('01'.repeat(32));
+        } catch (e) {
+          error = e;
+        }
+        expect(error.kind).toBe(wasmModule.UtreexoErrorKind.Unsupported);
+      }
+    });
+
+    it('should report serialized sizes without serializing', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      expect(stump.serialized_size()).toBe(stump.to_bytes().length);
+      expect(pollard.serialized_size()).toBe(pollard.serialize().length);
+
+      stump.apply_random_ops(11n, 20);
+      expect(stump.serialized_size()).toBe(stump.to_bytes().length);
+
+      const leaves = ['aa', 'bb', 'cc'].map(byte => byte.repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      expect(pollard.serialized_size()).toBe(pollard.serialize().length);
+    });
+
+    it('should only apply a block that ends at the expected roots', () => {
+      const source = new wasmModule.WasmStump();
+      const replay = new wasmModule.WasmStump();
+      const checked = new wasmModule.WasmStump();
+      wasmObjects.push(source, replay, checked);
+
+      const log = source.apply_random_ops(3n, 8);
+      const expected = source.roots();
+      log.slice(0, -1).forEach((op: any) => {
+        replay.modify(JSON.stringify(op.proof), op.add, op.del);
+        checked.apply_and_check(JSON.stringify(op.proof), op.add, op.del, replay.roots());
+      });
+      const last = log[log.length - 1];
+      const before = checked.roots();
+      const leavesBefore = checked.num_leaves();
+
+      let error: any;
+      try {
+        checked.apply_and_check(JSON.stringify(last.proof), last.add, last.del, ['ab'.repeat(32)]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.RootsMismatch);
+      expect(checked.roots()).toEqual(before);
+      expect(checked.num_leaves()).toBe(leavesBefore);
+
+      checked.apply_and_check(JSON.stringify(last.proof), last.add, last.del, expected);
+      expect(checked.roots()).toEqual(expected);
+    });
+
+    it('should verify a proof chain and point at the first bad block', () => {
+      const start = new wasmModule.WasmStump();
+      const source = new wasmModule.WasmStump();
+      const replay = new wasmModule.WasmStump();
+      const chain = new wasmModule.ProofChain();
+      wasmObjects.push(start, source, replay, chain);
+
+      const log = source.apply_random_ops(7n, 12);
+      const rootsAfter: string[][] = [];
+      for (const op of log) {
+        const proof = JSON.stringify(op.proof);
+        replay.modify(proof, op.add, op.del);
+        rootsAfter.push(replay.roots());
+        chain.push(proof, op.del, rootsAfter[rootsAfter.length - 1], op.add);
+      }
+      expect(chain.len()).toBe(12);
+
+      const decoded = wasmModule.ProofChain.from_bytes(chain.to_bytes());
+      const end = decoded.verify_chain(start);
+      wasmObjects.push(decoded, end);
+      expect(end.roots()).toEqual(source.roots());
+      expect(start.num_leaves()).toBe(0n);
+
+      const broken = new wasmModule.ProofChain();
+      wasmObjects.push(broken);
+      log.forEach((op: any, i: number) => {
+        broken.push(JSON.stringify(op.proof), op.del, i === 5 ? ['ab'.repeat(32)] : rootsAfter[i], op.add);
+      });
+      let error: any;
+      try {
+        broken.verify_chain(start);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.RootsMismatch);
+      expect(error.message).toMatch(/^Block 5:/);
+    });
+
+    it('should round trip accumulators through transferable JSON', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 7 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 6).map(hash => ({ hash, remember: true }))), []);
+      pollard.add_leaf_with_data(leaves[6], true, new Uint8Array([1, 2, 3]));
+      stump.modify(emptyProof, leaves, []);
+
+      const stumpJson = stump.to_transferable_json();
+      expect(JSON.parse(stumpJson).type).toBe('stump');
+      const stumpCopy = wasmModule.WasmStump.from_transferable_json(stumpJson);
+      const pollardCopy = wasmModule.WasmPollard.from_transferable_json(pollard.to_transferable_json());
+      wasmObjects.push(stumpCopy, pollardCopy);
+
+      expect(stumpCopy.roots()).toEqual(stump.roots());
+      expect(stumpCopy.sequence()).toBe(stump.sequence());
+      expect(pollardCopy.num_leaves()).toBe(7n);
+      expect(pollardCopy.roots()).toEqual(pollard.roots());
+      expect(pollardCopy.remembered_leaves_page(0, 10)).toEqual(leaves);
+      expect(Array.from(pollardCopy.leaf_data(leaves[6])!)).toEqual([1, 2, 3]);
+      expect(stump.verify(pollardCopy.prove_single(leaves[2]), [leaves[2]])).toBe(true);
+
+      expect(() => wasmModule.WasmPollard.from_transferable_json(stumpJson)).toThrow();
+      const tampered = JSON.parse(pollard.to_transferable_json());
+      tampered.remembered[0].hash = 'f'.repeat(64);
+      expect(() => wasmModule.WasmPollard.from_transferable_json(JSON.stringify(tampered))).toThrow();
+    });
+
+    it('should round trip transferable JSON after a whole tree is deleted', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+      const leaves = Array.from({ length: 3 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      // leaves[2] is a tree of its own, so deleting it leaves an empty root
+      stump.modify(pollard.prove_single(leaves[2]), [], [leaves[2]]);
+      pollard.delete([leaves[2]]);
+      expect(stump.roots()).toContain('empty');
+
+      const stumpCopy = wasmModule.WasmStump.from_transferable_json(stump.to_transferable_json());
+      const pollardCopy = wasmModule.WasmPollard.from_transferable_json(pollard.to_transferable_json());
+      wasmObjects.push(stumpCopy, pollardCopy);
+      expect(stumpCopy.roots()).toEqual(stump.roots());
+      expect(pollardCopy.roots()).toEqual(pollard.roots());
+      expect(stumpCopy.verify(pollardCopy.prove_single(leaves[0]), [leaves[0]])).toBe(true);
+    });
+
+    it('should give up on batch proofs that run over their time budget', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 16 }, (_, i) => (i + 1).toString(16).padStart(64, '0'));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const targets = [leaves[1], leaves[9], leaves[14]];
+      const budgeted = pollard.batch_proof(targets, 60_000);
+      expect(wasmModule.proofs_equivalent(budgeted, pollard.batch_proof(targets))).toBe(true);
+      expect(stump.verify(budgeted, targets)).toBe(true);
+
+      let error: any;
+      try {
+        pollard.batch_proof(targets, -1);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.Timeout);
+    });
+
+    it('should reject leaf counts too big for the position math', () => {
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+      const overflow = wasmModule.UtreexoErrorKind.Overflow;
+      const root = 'ab'.repeat(32);
+      const proof = JSON.stringify({ targets: [0], hashes: [] });
+
+      expect(kindOf(() => wasmModule.WasmStump.from_header_roots([root], 1n << 63n))).toBe(overflow);
+      expect(kindOf(() => wasmModule.WasmPollard.from_roots([root], 1n << 63n))).toBe(overflow);
+      expect(kindOf(() => wasmModule.WasmStump.from_json('{"leaves":18446744073709551615,"roots":[]}'))).toBe(overflow);
+      expect(kindOf(() => wasmModule.estimate_proof_hashes(new BigUint64Array([0n]), 2n ** 64n - 1n))).toBe(overflow);
+      expect(kindOf(() => wasmModule.merge_proofs(proof, proof, 1n << 63n))).toBe(overflow);
+
+      const stump = wasmModule.WasmStump.from_header_roots([root], 1n << 62n);
+      wasmObjects.push(stump);
+      expect(kindOf(() => stump.add(['cd'.repeat(32)]))).toBe(overflow);
+      expect(stump.num_leaves()).toBe(1n << 62n);
+    });
+
+    it('should refuse deletions in forests too tall for upstream position math', () => {
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+      const overflow = wasmModule.UtreexoErrorKind.Overflow;
+      const root = 'ab'.repeat(32);
+      const proof = JSON.stringify({ targets: [0], hashes: [] });
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+
+      const tall = wasmModule.WasmStump.from_header_roots([root], 1n << 30n);
+      const emptied = wasmModule.WasmStump.from_json(JSON.stringify({ leaves: 1 << 30, roots: ['Empty'] }));
+      const short = wasmModule.WasmStump.from_header_roots([root], 1n << 29n);
+      wasmObjects.push(tall, emptied, short);
+
+      expect(kindOf(() => tall.modify(proof, [], [root]))).toBe(overflow);
+      expect(kindOf(() => emptied.modify(emptyProof, ['cd'.repeat(32)], []))).toBe(overflow);
+      expect(emptied.num_leaves()).toBe(1n << 30n);
+      // Pollards built on those stumps refuse the same, whichever call mutates them
+      const tallPollard = wasmModule.WasmPollard.from_stump(tall);
+      const emptiedPollard = wasmModule.WasmPollard.from_stump(emptied);
+      wasmObjects.push(tallPollard, emptiedPollard);
+      expect(kindOf(() => tallPollard.modify(proof, '[]', [root]))).toBe(overflow);
+      expect(kindOf(() => emptiedPollard.add_and_prove('cd'.repeat(32)))).toBe(overflow);
+      expect(kindOf(() => emptiedPollard.add_leaf_with_data('cd'.repeat(32), true, new Uint8Array([1])))).toBe(overflow);
+      expect(emptiedPollard.num_leaves()).toBe(1n << 30n);
+      // Plain additions over a full root don't touch the broken math
+      tall.modify(emptyProof, ['cd'.repeat(32)], []);
+      expect(tall.num_leaves()).toBe((1n << 30n) + 1n);
+      expect(kindOf(() => short.modify(proof, [], [root]))).toBe(wasmModule.UtreexoErrorKind.LeafCountMismatch);
+    });
+
+    it('should page through remembered leaves', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 10 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      expect(pollard.remembered_count()).toBe(10);
+      const pages = [0, 4, 8].map(offset => pollard.remembered_leaves_page(offset, 4));
+      expect(pages.map(page => page.length)).toEqual([4, 4, 2]);
+      expect(pages.flat()).toEqual(leaves);
+      expect(pollard.remembered_leaves_page(4, 4)).toEqual(pages[1]);
+      expect(pollard.remembered_leaves_page(20, 4)).toEqual([]);
+    });
+
+    it('should reject duplicate additions on request', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const additions = (hashes: string[]) => JSON.stringify(hashes.map(hash => ({ hash, remember: true })));
+      pollard.modify(emptyProof, additions(leaves), [], true);
+
+      let error: any;
+      try {
+        pollard.modify(emptyProof, additions([leaves[1]]), [], true);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.DuplicateLeaf);
+      expect(() => pollard.modify(emptyProof, additions(['9'.repeat(64), '9'.repeat(64)]), [], true)).toThrow();
+      expect(pollard.num_leaves()).toBe(4n);
+
+      pollard.modify(emptyProof, additions([leaves[1]]), []);
+      expect(pollard.num_leaves()).toBe(5n);
+    });
+
+    it('should tell whether a proof bundle is still fresh', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const bundle = pollard.prove_bundle([leaves[3]]);
+      const manual = new wasmModule.WasmProofBundle(bundle.proof, [leaves[3]]);
+      wasmObjects.push(bundle, manual);
+      expect(bundle.valid_at_leaves).toBe(8n);
+      expect(bundle.valid_at_state_hash).toHaveLength(64);
+      expect(manual.valid_at_leaves).toBeUndefined();
+      expect(wasmModule.is_proof_fresh(bundle, stump)).toBe(true);
+      expect(wasmModule.is_proof_fresh(manual, stump)).toBe(false);
+
+      stump.modify(emptyProof, ['9'.repeat(64)], []);
+      expect(wasmModule.is_proof_fresh(bundle, stump)).toBe(false);
+    });
+
+    it('should verify with memoized nodes and reset the cache on stump changes', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      const cache = new wasmModule.NodeCache();
+      wasmObjects.push(pollard, stump, cache);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      leaves.forEach(leaf => {
+        expect(stump.verify_cached(pollard.prove_single(leaf), [leaf], cache)).toBe(true);
+      });
+      const size = cache.size;
+      expect(stump.verify_cached(pollard.prove_single(leaves[0]), [leaves[0]], cache)).toBe(true);
+      expect(cache.size).toBe(size);
+      expect(stump.verify_cached(pollard.prove_single(leaves[0]), [leaves[1]], cache)).toBe(false);
+
+      stump.modify(emptyProof, ['ff'.repeat(32)], []);
+      expect(stump.verify_cached(pollard.prove_single(leaves[0]), [leaves[0]], cache)).toBe(true);
+      expect(cache.size).toBeLessThan(size);
+    });
+
+    it('should return the merkle path from a leaf to its root', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i < 6 }))), []);
+
+      const path = pollard.merkle_path(leaves[5]);
+      expect(path).toHaveLength(4);
+      expect(path[0]).toEqual({ hash: leaves[5], sibling: leaves[4], is_left: false });
+      expect(path[3]).toEqual({ hash: pollard.roots()[0], sibling: null, is_left: null });
+
+      const leaf = new wasmModule.Hash(leaves[5]);
+      const sibling = new wasmModule.Hash(leaves[4]);
+      const parent = wasmModule.Hash.parent_hash(sibling, leaf);
+      wasmObjects.push(leaf, sibling, parent);
+      expect(path[1].hash).toBe(parent.to_hex());
+
+      expect(() => pollard.merkle_path(leaves[6])).toThrow();
+    });
+
+    it('should verify wire proofs like their JSON form', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proofJson = pollard.batch_proof([leaves[1], leaves[6]]);
+      const proof = JSON.parse(proofJson);
+
+      // LE target count and targets, then LE hash count and tagged 32-byte hashes
+      const wire = Buffer.alloc(16 + proof.targets.length * 8 + proof.hashes.length * 33);
+      let offset = wire.writeBigUInt64LE(BigInt(proof.targets.length), 0);
+      proof.targets.forEach((target: number) => {
+        offset = wire.writeBigUInt64LE(BigInt(target), offset);
+      });
+      offset = wire.writeBigUInt64LE(BigInt(proof.hashes.length), offset);
+      proof.hashes.forEach((hash: any) => {
+        offset = wire.writeUInt8(2, offset);
+        offset += Buffer.from(hash.Some).copy(wire, offset);
+      });
+
+      const hashBytes = [leaves[1], leaves[6]].map(hex => new Uint8Array(Buffer.from(hex, 'hex')));
+      expect(stump.verify_wire(new Uint8Array(wire), hashBytes))
+        .toBe(stump.verify(proofJson, [leaves[1], leaves[6]]));
+      expect(() => stump.verify_wire(new Uint8Array(wire.subarray(0, 10)), hashBytes)).toThrow();
+    });
+
+    it('should verify and modify with proofs given as JSON bytes', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      const twin = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump, twin);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyBytes = new TextEncoder().encode(JSON.stringify({ targets: [], hashes: [] }));
+      pollard.modify_bytes(emptyBytes, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify_bytes(emptyBytes, leaves, []);
+      twin.modify(JSON.stringify({ targets: [], hashes: [] }), leaves, []);
+      expect(stump.roots()).toEqual(twin.roots());
+
+      const proofJson = pollard.prove_single(leaves[2]);
+      const proofBytes = new TextEncoder().encode(proofJson);
+      expect(stump.verify_bytes(proofBytes, [leaves[2]])).toBe(true);
+      expect(pollard.verify_bytes(proofBytes, [leaves[2]])).toBe(true);
+      expect(stump.verify_and_roots_bytes(proofBytes, [leaves[2]]))
+        .toEqual(stump.verify_and_roots(proofJson, [leaves[2]]));
+
+      stump.modify_bytes(proofBytes, [], [leaves[2]]);
+      twin.modify(proofJson, [], [leaves[2]]);
+      expect(stump.roots()).toEqual(twin.roots());
+
+      expect(() => stump.verify_bytes(new TextEncoder().encode('{"targets":'), [leaves[2]])).toThrow();
+    });
+
+    it('should merge proofs and detect conflicting ones', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proofA = pollard.prove_single(leaves[0]);
+      const proofB = pollard.prove_single(leaves[2]);
+      const merged = wasmModule.merge_proofs(proofA, proofB, 8n);
+      expect(JSON.parse(merged).targets).toEqual([0, 2]);
+      expect(stump.verify_and_roots(merged, [leaves[0], leaves[2]])).toEqual(stump.roots());
+
+      // Both proofs carry the hash of the right half of the tree
+      const tampered = JSON.parse(proofB);
+      tampered.hashes[2].Some[0] ^= 1;
+      let error: any;
+      try {
+        wasmModule.merge_proofs(proofA, JSON.stringify(tampered), 8n);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.ProofConflict);
+    });
+
+    it('should tell equivalent proof encodings apart from different proofs', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const forward = pollard.batch_proof([leaves[1], leaves[6]]);
+      const backward = pollard.batch_proof([leaves[6], leaves[1]]);
+      const spaced = JSON.stringify(JSON.parse(forward), null, 2);
+      expect(forward).not.toBe(backward);
+      expect(wasmModule.proofs_equivalent(forward, backward)).toBe(true);
+      expect(wasmModule.proofs_equivalent(forward, spaced)).toBe(true);
+      expect(wasmModule.proofs_equivalent(forward, pollard.batch_proof([leaves[1]]))).toBe(false);
+    });
+
+    it('should convert proofs to multiproofs and back', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.batch_proof([leaves[5], leaves[0], leaves[1]]);
+      const multiproof = wasmModule.proof_to_multiproof(proof, 8n);
+      expect(multiproof.leaves_positions).toEqual([0, 1, 5]);
+      expect(multiproof.flags).toEqual([true, false, false, false, true]);
+      expect(multiproof.proof_hashes).toHaveLength(3);
+
+      const back = wasmModule.multiproof_to_proof(JSON.stringify(multiproof), 8n);
+      expect(stump.verify(back, [leaves[0], leaves[1], leaves[5]])).toBe(true);
+
+      const tampered = { ...multiproof, flags: [false, ...multiproof.flags.slice(1)] };
+      expect(() => wasmModule.multiproof_to_proof(JSON.stringify(tampered), 8n)).toThrow();
+    });
+
+    it('should keep multiproof hashes lowercase with uppercase hex on', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      const proof = pollard.batch_proof([leaves[0], leaves[5]]);
+      const expected = wasmModule.proof_to_multiproof(proof, 8n);
+
+      try {
+        wasmModule.set_hex_uppercase(true);
+        const multiproof = wasmModule.proof_to_multiproof(proof, 8n);
+        expect(JSON.stringify(multiproof)).toBe(JSON.stringify(expected));
+        expect(multiproof.proof_hashes.every((hash: string) => hash === hash.toLowerCase())).toBe(true);
+      } finally {
+        wasmModule.set_hex_uppercase(false);
+      }
+    });
+
+    it('should verify against an overridden leaf count', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      pollard.modify(JSON.stringify({ targets: [], hashes: [] }),
+        JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.add(leaves);
+
+      const proof = pollard.prove_single(leaves[2]);
+      expect(stump.verify_with_leaves(proof, [leaves[2]])).toBe(true);
+      expect(stump.verify_with_leaves(proof, [leaves[2]], 6n)).toBe(true);
+      // Same roots, but the trees they belong to are laid out differently
+      expect(stump.verify_with_leaves(proof, [leaves[2]], 3n)).toBe(false);
+      expect(() => stump.verify_with_leaves(proof, [leaves[2]], 100n)).toThrow();
+      expect(() => stump.verify_with_leaves(proof, [leaves[2]], 0n)).toThrow();
+    });
+
+    it('should verify without throwing through try_verify', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const proof = pollard.prove_single(leaves[2]);
+      expect(stump.try_verify(proof, [leaves[2]])).toEqual({ ok: true, valid: true, error: null });
+      expect(stump.try_verify(proof, [leaves[3]])).toEqual({ ok: true, valid: false, error: null });
+      expect(stump.verify(proof, [leaves[3]])).toBe(false);
+
+      const broken = stump.try_verify('{"targets":', [leaves[2]]);
+      expect(broken.ok).toBe(false);
+      expect(broken.valid).toBe(false);
+      expect(broken.error).toMatch(/Failed to parse proof JSON/);
+      expect(stump.try_verify(proof, ['not a hash']).ok).toBe(false);
+    });
+
+    it('should verify one proof against several candidate stumps', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const tip = new wasmModule.WasmStump();
+      const fork = new wasmModule.WasmStump();
+      const longer = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, tip, fork, longer);
+
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      tip.modify(emptyProof, leaves, []);
+      fork.modify(emptyProof, [...leaves.slice(0, 5), 'f'.repeat(64)], []);
+      longer.modify(emptyProof, [...leaves, 'e'.repeat(64), 'd'.repeat(64)], []);
+
+      const stumps = [tip, fork, longer].map(stump => stump.to_json());
+      expect(wasmModule.verify_against_many(stumps, pollard.prove_single(leaves[5]), [leaves[5]]))
+        .toEqual([true, false, false]);
+      expect(wasmModule.verify_against_many(stumps, pollard.prove_single(leaves[0]), [leaves[0]]))
+        .toEqual([true, true, false]);
+    });
+
+    it('should estimate the proof hash count of a batch proof', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const leaves = Array.from({ length: 7 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      const proof = JSON.parse(pollard.batch_proof([leaves[0], leaves[2], leaves[3]]));
+      expect(wasmModule.estimate_proof_hashes(new BigUint64Array([0n, 2n, 3n]), 7n))
+        .toBe(proof.hashes.length);
+
+      expect(() => wasmModule.estimate_proof_hashes(new BigUint64Array([7n]), 7n)).toThrow();
+    });
+  });
+
+  describe('Error Handling', () => {
+    it('should handle invalid JSON in stump operations', () => {
+      expect(() => {
+        wasmModule.WasmStump.from_json('invalid json');
+      }).toThrow();
+      
+      expect(() => {
+        wasmModule.WasmStump.from_json('{}'); // Missing required fields
+      }).toThrow();
+    });
+
+    it('should handle invalid proof formats', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      
+      const invalidProofs = [
+        'invalid json',
+        '{}', // Missing required fields
+        '{"targets": []}', // Missing other fields
+        '{"proof": []}' // Missing other fields
+      ];
+      
+      invalidProofs.forEach(invalidProof => {
+        expect(() => {
+          stump.verify(invalidProof, []);
+        }).toThrow();
+        
+        expect(() => {
+          pollard.verify(invalidProof, []);
+        }).toThrow();
+      });
+    });
+
+    it('should report an empty accumulator for proofs with targets', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      const proof = JSON.stringify({ targets: [0], hashes: [] });
+      const leaf = 'deadbeefcafebabe0123456789abcdef0123456789abcdef0123456789abcdef';
+      const kinds = [
+        () => stump.verify(proof, [leaf]),
+        () => stump.modify(proof, [], [leaf]),
+        () => pollard.verify(proof, [leaf]),
+      ].map((call) => {
+        try {
+          call();
+        } catch (e: any) {
+          return e.kind;
+        }
+      });
+
+      expect(kinds).toEqual(Array(3).fill(wasmModule.UtreexoErrorKind.EmptyAccumulator));
+    });
+
+    it('should report proofs made at another leaf count', () => {
+      const small = new wasmModule.WasmPollard();
+      const large = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(small, large, stump);
+
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const additions = (hashes: string[]) => JSON.stringify(hashes.map(hash => ({ hash, remember: true })));
+      small.modify(emptyProof, additions(leaves.slice(0, 5)), []);
+      large.modify(emptyProof, additions(leaves), []);
+      stump.modify(emptyProof, leaves.slice(0, 5), []);
+
+      const kindOf = (call: () => unknown) => {
+        try {
+          call();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+
+      // Position 6 doesn't exist among 5 leaves
+      expect(kindOf(() => stump.verify(large.prove_single(leaves[6]), [leaves[6]])))
+        .toBe(wasmModule.UtreexoErrorKind.LeafCountMismatch);
+
+      // Proving leaf 0 among 8 leaves takes one more hash than among 5
+      stump.modify(emptyProof, leaves.slice(5), []);
+      expect(kindOf(() => stump.verify(small.prove_single(leaves[0]), [leaves[0]])))
+        .toBe(wasmModule.UtreexoErrorKind.LeafCountMismatch);
+      expect(stump.verify(large.prove_single(leaves[0]), [leaves[0]])).toBe(true);
+    });
+
+    it('should size batch proofs that verify after a deletion', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      stump.modify(pollard.prove_single(leaves[8]), [], [leaves[8]]);
+      pollard.delete([leaves[8]]);
+
+      const targets = [leaves[6], leaves[0], leaves[3]];
+      const { proof, per_target_bytes } = pollard.batch_proof_with_sizes(targets);
+      expect(stump.verify(proof, targets)).toBe(true);
+      expect(per_target_bytes).toHaveLength(3);
+      const total = per_target_bytes.reduce((sum: number, bytes: number) => sum + bytes, 0);
+      expect(total).toBeCloseTo(pollard.batch_proof_encoded(targets).wire.length);
+    });
+
+    it('should encode batch proofs that verify after a deletion', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      stump.modify(pollard.prove_single(leaves[8]), [], [leaves[8]]);
+      pollard.delete([leaves[8]]);
+
+      const targets = [leaves[6], leaves[0], leaves[3]];
+      const { json, wire } = pollard.batch_proof_encoded(targets);
+      expect(json).toBe(pollard.batch_proof(targets));
+      expect(stump.verify(json, targets)).toBe(true);
+      const hashBytes = targets.map(hex => new Uint8Array(Buffer.from(hex, 'hex')));
+      expect(stump.verify_wire(wire, hashBytes)).toBe(true);
+    });
+
+    it('should build bundles that verify after a deletion', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).padStart(2, '0').repeat(32));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+
+      stump.modify(pollard.prove_single(leaves[8]), [], [leaves[8]]);
+      pollard.delete([leaves[8]]);
+
+      const bundle = pollard.prove_bundle([leaves[1], leaves[6], leaves[4]]);
+      wasmObjects.push(bundle);
+      expect(stump.verify_bundle(bundle)).toBe(true);
+      expect(pollard.verify_bundle(bundle)).toBe(true);
+      expect(stump.verify(pollard.batch_proof([leaves[1], leaves[6], leaves[4]]), [leaves[1], leaves[6], leaves[4]])).toBe(true);
+    });
+
+    it('should report stale and malformed proofs the same way from a pollard', () => {
+      const small = new wasmModule.WasmPollard();
+      const large = new wasmModule.WasmPollard();
+      wasmObjects.push(small, large);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const additions = (hashes: string[]) => JSON.stringify(hashes.map(hash => ({ hash, remember: true })));
+      small.modify(emptyProof, additions(leaves.slice(0, 5)), []);
+      large.modify(emptyProof, additions(leaves), []);
+
+      const kindOf = (call: () => unknown) => {
+        try {
+          call();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+
+      const stale = large.prove_single(leaves[6]);
+      const staleProof = wasmModule.WasmProof.from_json(stale);
+      wasmObjects.push(staleProof);
+      const staleBytes = new TextEncoder().encode(stale);
+      for (const call of [
+        () => small.verify(stale, [leaves[6]]),
+        () => small.verify_proof(staleProof, [leaves[6]]),
+        () => small.verify_bytes(staleBytes, [leaves[6]]),
+        () => small.verify_raw(stale, Uint8Array.from(Buffer.from(leaves[6], 'hex'))),
+      ]) {
+        expect(kindOf(call)).toBe(wasmModule.UtreexoErrorKind.LeafCountMismatch);
+      }
+
+      const parsed = JSON.parse(small.prove_single(leaves[0]));
+      const padded = JSON.stringify({ ...parsed, hashes: [...parsed.hashes, ...parsed.hashes, ...parsed.hashes] });
+      expect(kindOf(() => small.verify(padded, [leaves[0]]))).toBe(wasmModule.UtreexoErrorKind.MalformedProof);
+    });
+
+    it('should accept the spare hash in proofs from a one leaf pollard', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+      const leaf = '1'.repeat(64);
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify([{ hash: leaf, remember: true }]), []);
+      stump.modify(emptyProof, [leaf], []);
+
+      // The lone leaf is its own root, yet prove_single still adds a sibling hash
+      const proof = pollard.prove_single(leaf);
+      expect(JSON.parse(proof).hashes).toHaveLength(1);
+      expect(stump.verify(proof, [leaf])).toBe(true);
+      expect(pollard.verify(proof, [leaf])).toBe(true);
+      stump.check_proof(proof, [leaf]);
+
+      const padded = JSON.stringify({ targets: [0], hashes: [leaf, leaf] });
+      let error: any;
+      try {
+        stump.verify(padded, [leaf]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.MalformedProof);
+    });
+
+    it('should report a distinct kind when deletions do not match the proof', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaf = 'deadbeefcafebabe0123456789abcdef0123456789abcdef0123456789abcdef';
+      stump.modify(emptyProof, [leaf], []);
+
+      let error: any;
+      try {
+        stump.modify(emptyProof, [], [leaf]);
+      } catch (e) {
+        error = e;
+      }
+
+      expect(error).toBeDefined();
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+      expect(stump.num_leaves()).toBe(1n);
+    });
+
+    it('should report deletions that do not match the proof from a pollard modify', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      const roots = pollard.roots();
+
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+      const proof = pollard.prove_single(leaves[1]);
+      expect(kindOf(() => pollard.modify(proof, '[]', []))).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+      expect(kindOf(() => pollard.modify(emptyProof, '[]', [leaves[1]]))).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+      expect(pollard.roots()).toEqual(roots);
+    });
+
+    it('should sort each modify failure into the kind of its cause', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      stump.modify(emptyProof, leaves, []);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      const roots = stump.roots();
+
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+        throw new Error('expected a failure');
+      };
+      const Kind = wasmModule.UtreexoErrorKind;
+      const proof = JSON.parse(pollard.prove_single(leaves[1]));
+      const json = (targets: number[], hashes: string[]) => JSON.stringify({ targets, hashes });
+
+      // Well formed, but the deleted hash isn't the leaf at the target
+      expect(kindOf(() => stump.modify(json(proof.targets, proof.hashes), [], [leaves[0]])))
+        .toBe(Kind.ProofVerificationFailed);
+      // More hashes than any path in the forest could use
+      expect(kindOf(() => stump.modify(json(proof.targets, [...proof.hashes, ...proof.hashes]), [], [leaves[1]])))
+        .toBe(Kind.MalformedProof);
+      // Missing a hash its path needs, as a proof made at a smaller leaf count is
+      expect(kindOf(() => stump.modify(json(proof.targets, proof.hashes.slice(1)), [], [leaves[1]])))
+        .toBe(Kind.LeafCountMismatch);
+      expect(kindOf(() => stump.modify(json(proof.targets, proof.hashes), [], [])))
+        .toBe(Kind.DeletionNotInProof);
+      expect(kindOf(() => stump.modify(json([1, 1], proof.hashes), [], [leaves[1], leaves[1]])))
+        .toBe(Kind.DeletionNotInProof);
+      expect(stump.roots()).toEqual(roots);
+
+      stump.modify(json(proof.targets, proof.hashes), [], [leaves[1]]);
+      expect(stump.roots()).not.toEqual(roots);
+    });
+  });
+
+  describe('Memory Management', () => {
+    it('should handle multiple object creation and cleanup', () => {
+      const objects = [];
+      
+      // Create many objects
+      for (let i = 0; i < 50; i++) {
+        const stump = new wasmModule.WasmStump();
+        const pollard = new wasmModule.WasmPollard();
+        objects.push(stump, pollard);
+        
+        expect(stump.num_leaves()).toBe(0n);
+        expect(pollard.num_leaves()).toBe(0n);
+      }
+      
+      expect(objects).toHaveLength(100);
+      
+      // Clean up
+      objects.forEach(obj => obj.free());
+    });
+
+    it('should handle repeated creation/destruction cycles', () => {
+      for (let cycle = 0; cycle < 10; cycle++) {
+        const stump = new wasmModule.WasmStump();
+        const pollard = new wasmModule.WasmPollard();
+        
+        expect(stump.num_leaves()).toBe(0n);
+        expect(pollard.num_leaves()).toBe(0n);
+        
+        stump.free();
+        pollard.free();
+      }
+    });
+  });
+
+  describe('Accumulator Modifications', () => {
+    it('should handle empty modifications without errors', () => {
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+      
+      const emptyProof = JSON.stringify({
+        targets: [],
+        proof: [],
+        hashes: []
+      });
+      
+      // These operations should not change the state
+      const initialStumpLeaves = stump.num_leaves();
+      const initialPollardLeaves = pollard.num_leaves();
+      
+      // For stump: modify(proof_json, add_hashes, del_hashes)
+      stump.modify(emptyProof, [], []);
+      
+      // For pollard: modify(proof_json, additions_json, del_hashes)
+      pollard.modify(emptyProof, JSON.stringify([]), []);
+      
+      expect(stump.num_leaves()).toBe(initialStumpLeaves);
+      expect(pollard.num_leaves()).toBe(initialPollardLeaves);
+    });
+
+    it('should return the positions of appended leaves', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 5 }, (_, i) => (i + 1).toString(16).repeat(64));
+      pollard.modify(JSON.stringify({ targets: [], hashes: [] }),
+        JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      expect(Array.from(stump.add(leaves))).toEqual([0n, 1n, 2n, 3n, 4n]);
+
+      const proof = pollard.prove_single(leaves[1]);
+      stump.modify(proof, [], [leaves[1]]);
+      pollard.modify(proof, '[]', [leaves[1]]);
+
+      // Positions keep counting from the total ever added, not the live leaves
+      const more = ['a'.repeat(64), 'b'.repeat(64)];
+      const positions = Array.from(stump.add(more));
+      expect(positions).toEqual([5n, 6n]);
+
+      pollard.modify(JSON.stringify({ targets: [], hashes: [] }),
+        JSON.stringify(more.map(hash => ({ hash, remember: true }))), []);
+      more.forEach((hash, i) => {
+        expect(BigInt(JSON.parse(pollard.prove_single(hash)).targets[0])).toBe(positions[i]);
+      });
+    });
+
+    it('should apply a block update to stumps and pollards alike', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const first = new wasmModule.WasmBlockUpdate(emptyProof,
+        JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i !== 3 }))), []);
+      wasmObjects.push(first);
+      pollard.apply_block(first);
+      stump.apply_block(first);
+      expect(first.remembered).toEqual([true, true, true, false]);
+
+      const second = new wasmModule.WasmBlockUpdate(pollard.prove_single(leaves[1]),
+        JSON.stringify([{ hash: 'a'.repeat(64), remember: true }]), [leaves[1]]);
+      const replayed = wasmModule.WasmBlockUpdate.from_json(second.to_json());
+      wasmObjects.push(second, replayed);
+      expect(replayed.deletions).toEqual([leaves[1]]);
+      expect(replayed.additions).toEqual(['a'.repeat(64)]);
+
+      pollard.apply_block(second);
+      stump.apply_block(replayed);
+      expect(stump.roots()).toEqual(pollard.roots().slice().reverse());
+      expect(pollard.num_leaves()).toBe(5n);
+
+      expect(() => new wasmModule.WasmBlockUpdate(emptyProof, '[]', [leaves[0]])).toThrow();
+    });
+
+    it('should report mismatched deletions from every checked pollard modify', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      const roots = pollard.roots();
+
+      const kindOf = (fn: () => unknown) => {
+        try {
+          fn();
+        } catch (e: any) {
+          return e.kind;
+        }
+      };
+      const proof = pollard.prove_single(leaves[1]);
+      const raw = Uint8Array.from(Buffer.from(leaves[1], 'hex'));
+      const mismatch = wasmModule.UtreexoErrorKind.DeletionNotInProof;
+      expect(kindOf(() => pollard.modify_bytes(new TextEncoder().encode(proof), '[]', []))).toBe(mismatch);
+      expect(kindOf(() => pollard.modify_raw(emptyProof, new Uint8Array(0), new Uint8Array(0), raw))).toBe(mismatch);
+      expect(kindOf(() => pollard.modify_with_deletions(proof, '[]', [leaves[1], leaves[2]]))).toBe(mismatch);
+      expect(pollard.roots()).toEqual(roots);
+    });
+
+    it('should replay an operation log into the same stump', () => {
+      const emptyProof = { targets: [], hashes: [] };
+      const leaves = ['1'.repeat(64), '2'.repeat(64), '3'.repeat(64)];
+
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      stump.modify(JSON.stringify(emptyProof), leaves.slice(0, 2), []);
+      stump.modify(JSON.stringify(emptyProof), leaves.slice(2), []);
+
+      const replayed = wasmModule.WasmStump.replay(JSON.stringify([
+        { add: leaves.slice(0, 2), proof: emptyProof },
+        { add: leaves.slice(2), del: [], proof: emptyProof }
+      ]));
+      wasmObjects.push(replayed);
+
+      expect(replayed.num_leaves()).toBe(3n);
+      expect(replayed.roots()).toEqual(stump.roots());
+
+      expect(() => wasmModule.WasmStump.replay(JSON.stringify([
+        { add: leaves, proof: emptyProof },
+        { del: [leaves[0]], proof: emptyProof }
+      ]))).toThrow(/Operation 1 failed/);
+    });
+
+    it('should report the leaf count after each replayed operation', () => {
+      const emptyProof = { targets: [], hashes: [] };
+      const leaves = ['1'.repeat(64), '2'.repeat(64), '3'.repeat(64)];
+
+      const counts = wasmModule.WasmStump.replay_leaf_counts(JSON.stringify([
+        { add: leaves.slice(0, 2), proof: emptyProof },
+        { proof: emptyProof },
+        { add: leaves.slice(2), proof: emptyProof }
+      ]));
+
+      expect(Array.from(counts)).toEqual([2n, 2n, 3n]);
+    });
+
+    it('should describe a modify with a WasmUpdate', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = ['1'.repeat(64), '2'.repeat(64)];
+      stump.modify(emptyProof, [leaves[0]], []);
+
+      const update = stump.modify_with_update(emptyProof, [leaves[1]], []);
+      wasmObjects.push(update);
+      expect(update.added).toEqual([leaves[1]]);
+      expect(update.deleted).toEqual([]);
+      expect(update.prev_num_leaves).toBe(1n);
+
+      const restored = wasmModule.WasmUpdate.from_json(update.to_json());
+      wasmObjects.push(restored);
+      expect(restored.added).toEqual(update.added);
+      expect(restored.to_json()).toBe(update.to_json());
+    });
+
+    it('should keep cached proofs valid across updates', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      const cache = new wasmModule.ProofCache();
+      wasmObjects.push(pollard, stump, cache);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      pollard.modify(emptyProof, JSON.stringify(leaves.slice(0, 4).map(hash => ({ hash, remember: true }))), []);
+      stump.modify(emptyProof, leaves.slice(0, 4), []);
+
+      cache.insert(leaves[0], pollard.prove_single(leaves[0]));
+      cache.insert(leaves[2], pollard.prove_single(leaves[2]));
+
+      const delProof = pollard.prove_single(leaves[2]);
+      const update = stump.modify_with_update(delProof, leaves.slice(4), [leaves[2]]);
+      wasmObjects.push(update);
+      cache.apply(update);
+
+      expect(cache.size).toBe(1);
+      expect(cache.get(leaves[2])).toBeUndefined();
+      expect(stump.verify_and_roots(cache.get(leaves[0]), [leaves[0]]).length).toBe(1);
+    });
+
+    it('should preview a modify without applying it', () => {
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+      stump.modify(emptyProof, leaves.slice(0, 3), []);
+
+      const before = stump.roots();
+      const preview = stump.preview_modify(emptyProof, leaves.slice(3), []);
+      expect(stump.roots()).toEqual(before);
+      expect(stump.num_leaves()).toBe(3n);
+      expect(preview.roots_before).toEqual(before);
+      expect(preview.merged_ranges).toEqual([
+        { start: 0, end: 4, row: 2 },
+        { start: 4, end: 6, row: 1 },
+      ]);
+
+      stump.modify(emptyProof, leaves.slice(3), []);
+      expect(preview.roots_after).toEqual(stump.roots());
+    });
+
+    it('should flag which stump roots changed after a modify', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 6 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.modify(emptyProof, leaves.slice(0, 5), []);
+
+      const before = stump.roots();
+      const changed = stump.modify_with_changes(emptyProof, leaves.slice(5), []);
+      const after = stump.roots();
+
+      expect(changed).toEqual([false, true]);
+      expect(after[0]).toBe(before[0]);
+    });
+
+    it('should call the roots changed callback after each modify', () => {
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const first: string[][] = [];
+      const second: string[][] = [];
+      pollard.on_roots_changed((roots: string[]) => first.push(roots));
+      pollard.modify(emptyProof, JSON.stringify([{ hash: '1'.repeat(64), remember: true }]), []);
+
+      pollard.on_roots_changed((roots: string[]) => second.push(roots));
+      pollard.modify(emptyProof, JSON.stringify([{ hash: '2'.repeat(64), remember: true }]), []);
+
+      expect(first).toEqual([['1'.repeat(64)]]);
+      expect(second).toEqual([pollard.roots()]);
+    });
+
+    it('should tell whether an op log leads from one stump to another', () => {
+      const emptyProof = { targets: [], hashes: [] };
+      const leaves = ['1'.repeat(64), '2'.repeat(64), '3'.repeat(64)];
+
+      const ancestor = new wasmModule.WasmStump();
+      const tip = new wasmModule.WasmStump();
+      wasmObjects.push(ancestor, tip);
+      ancestor.modify(JSON.stringify(emptyProof), [leaves[0]], []);
+      tip.modify(JSON.stringify(emptyProof), leaves, []);
+
+      const log = JSON.stringify([{ add: leaves.slice(1), proof: emptyProof }]);
+      const otherLog = JSON.stringify([{ add: [leaves[2], leaves[1]], proof: emptyProof }]);
+      expect(ancestor.is_ancestor_of(tip, log)).toBe(true);
+      expect(ancestor.is_ancestor_of(tip, otherLog)).toBe(false);
+      expect(ancestor.num_leaves()).toBe(1n);
+    });
+
+    it('should keep roots and proofs unchanged when compacting', () => {
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 12 }, (_, i) => (i + 1).toString(16).repeat(64));
+
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i % 3 !== 0 }))), []);
+      const dels = [leaves[1], leaves[4]];
+      pollard.modify(pollard.batch_proof(dels), JSON.stringify([]), dels);
+
+      const remaining = leaves.filter(leaf => !dels.includes(leaf));
+      const prove = (leaf: string) => {
+        try {
+          return pollard.prove_single(leaf);
+        } catch (e) {
+          return null;
+        }
+      };
+      const roots = pollard.roots();
+      const proofs = remaining.map(prove);
+
+      pollard.compact();
+      expect(pollard.roots()).toEqual(roots);
+      expect(remaining.map(prove)).toEqual(proofs);
+      expect(proofs.filter(proof => proof === null)).toHaveLength(4);
+    });
+
+    it('should add a leaf and prove it in one call', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 5 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const proofs = leaves.map((leaf: string) => pollard.add_and_prove(leaf));
+      stump.modify(JSON.stringify({ targets: [], hashes: [] }), leaves, []);
+
+      expect(pollard.num_leaves()).toBe(5n);
+      expect(JSON.parse(proofs[4]).targets).toEqual([4]);
+      expect(stump.verify(pollard.prove_single(leaves[0]), [leaves[0]])).toBe(true);
+      expect(stump.verify(proofs[4], [leaves[4]])).toBe(true);
+    });
+
+    it('should delete remembered leaves without an external proof', () => {
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+      pollard.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i < 6 }))), []);
+      stump.modify(emptyProof, leaves, []);
+
+      const dels = [leaves[5], leaves[2]];
+      stump.modify(pollard.batch_proof(dels), [], dels);
+      pollard.delete(dels);
+      expect(pollard.roots()).toEqual(stump.roots());
+
+      let error: any;
+      try {
+        pollard.delete([leaves[7]]);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.DeletionNotInProof);
+    });
+
+    it('should carry leaf data until the leaf is deleted', () => {
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(pollard);
+      leaves.forEach((leaf, i) => pollard.add_leaf_with_data(leaf, i < 3, Uint8Array.of(i, 42)));
+
+      expect(pollard.leaf_data(leaves[1])).toEqual(Uint8Array.of(1, 42));
+      expect(pollard.leaf_data(leaves[3])).toBeUndefined();
+
+      pollard.delete([leaves[1]]);
+      expect(pollard.leaf_data(leaves[1])).toBeUndefined();
+      expect(pollard.leaf_data(leaves[0])).toEqual(Uint8Array.of(0, 42));
+    });
+
+    it('should delete forgotten leaves given a supplementary proof', () => {
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      const leaves = Array.from({ length: 8 }, (_, i) => (i + 1).toString(16).repeat(64));
+
+      const full = new wasmModule.WasmPollard();
+      const light = new wasmModule.WasmPollard();
+      wasmObjects.push(full, light);
+      full.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      light.modify(emptyProof, JSON.stringify(leaves.map((hash, i) => ({ hash, remember: i === 7 }))), []);
+
+      const dels = [leaves[7], leaves[0]];
+      const supplement = full.batch_proof([leaves[0]]);
+      full.modify(full.batch_proof(dels), JSON.stringify([]), dels);
+      light.modify_with_supplement(supplement, JSON.stringify([]), dels);
+
+      expect(light.roots()).toEqual(full.roots());
+    });
+  });
+
+  describe('Serialization', () => {
+    // A stump persisted with to_json before binary formats were versioned
+    const legacyStump =
+      '{"leaves":2,"roots":[{"Some":[110,52,11,156,255,179,122,152,156,165,68,230,187,120,10,44,120,144,29,63,179,55,56,118,133,17,163,6,23,175,160,29]}]}';
+
+    // The same stump as written by format version 1, before stumps carried a sequence
+    const v1Stump =
+      '010102000000000000000100000000000000026e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d';
+
+    it('should migrate an unversioned stump blob', () => {
+      const migrated = wasmModule.migrate(new TextEncoder().encode(legacyStump));
+      expect(migrated[0]).toBe(2);
+
+      const stump = wasmModule.WasmStump.from_bytes(migrated);
+      const direct = wasmModule.WasmStump.from_bytes(new TextEncoder().encode(legacyStump));
+      wasmObjects.push(stump, direct);
+      expect(stump.num_leaves()).toBe(2n);
+      expect(stump.roots()).toEqual(['6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d']);
+      expect(direct.to_bytes()).toEqual(migrated);
+      expect(wasmModule.migrate(migrated)).toEqual(migrated);
+    });
+
+    it('should migrate a version 1 stump blob', () => {
+      const v1 = Uint8Array.from(Buffer.from(v1Stump, 'hex'));
+      const migrated = wasmModule.migrate(v1);
+      expect(migrated[0]).toBe(2);
+
+      const stump = wasmModule.WasmStump.from_bytes(migrated);
+      const direct = wasmModule.WasmStump.from_bytes(v1);
+      const legacy = wasmModule.WasmStump.from_bytes(new TextEncoder().encode(legacyStump));
+      wasmObjects.push(stump, direct, legacy);
+      expect(stump.num_leaves()).toBe(2n);
+      expect(stump.roots()).toEqual(['6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d']);
+      expect(stump.sequence()).toBe(0n);
+      expect(direct.to_bytes()).toEqual(migrated);
+      expect(migrated).toEqual(legacy.to_bytes());
+    });
+
+    it('should count applied modifies and keep the count across round-trips', () => {
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(stump);
+
+      const leaves = Array.from({ length: 4 }, (_, i) => (i + 1).toString(16).repeat(64));
+      stump.add(leaves.slice(0, 3));
+      stump.add(leaves.slice(3));
+      expect(() => stump.modify(JSON.stringify({ targets: [], hashes: [] }), [], [leaves[0]])).toThrow();
+      expect(stump.sequence()).toBe(2n);
+
+      const fromJson = wasmModule.WasmStump.from_json(stump.to_json());
+      const fromBytes = wasmModule.WasmStump.from_bytes(stump.to_bytes());
+      const legacy = wasmModule.WasmStump.from_bytes(new TextEncoder().encode(legacyStump));
+      wasmObjects.push(fromJson, fromBytes, legacy);
+      expect(fromJson.sequence()).toBe(2n);
+      expect(fromBytes.sequence()).toBe(2n);
+      expect(legacy.sequence()).toBe(0n);
+    });
+
+    it('should export and import just a wallet\'s leaves', () => {
+      const pollard = new wasmModule.WasmPollard();
+      const stump = new wasmModule.WasmStump();
+      wasmObjects.push(pollard, stump);
+
+      const leaves = Array.from({ length: 13 }, (_, i) => (i + 1).toString(16).padStart(64, '0'));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+      pollard.modify(emptyProof, JSON.stringify(leaves.map(hash => ({ hash, remember: true }))), []);
+      stump.add(leaves);
+
+      const mine = [leaves[0], leaves[5], leaves[12]];
+      const bytes = pollard.export_wallet_state(mine);
+      const restored = wasmModule.WasmPollard.import_wallet_state(bytes);
+      wasmObjects.push(restored);
+
+      expect(restored.roots()).toEqual(pollard.roots());
+      expect(restored.num_leaves()).toBe(13n);
+      mine.forEach(leaf => {
+        expect(stump.verify(restored.prove_single(leaf), [leaf])).toBe(true);
+      });
+      expect(() => restored.prove_single(leaves[9])).toThrow();
+
+      const tampered = Uint8Array.from(bytes);
+      tampered[tampered.length - 1] ^= 1;
+      expect(() => wasmModule.WasmPollard.import_wallet_state(tampered)).toThrow();
+      expect(() => pollard.export_wallet_state(['f'.repeat(64)])).toThrow();
+    });
+
+    it('should round-trip proofs and reject unknown versions', () => {
+      const proof = JSON.stringify({ targets: [0], hashes: [] });
+      const bytes = wasmModule.proof_to_bytes(proof);
+      expect(JSON.parse(wasmModule.proof_from_bytes(bytes))).toEqual(JSON.parse(proof));
+
+      const future = Uint8Array.from(bytes);
+      future[0] = 99;
+      let error: any;
+      try {
+        wasmModule.migrate(future);
+      } catch (e) {
+        error = e;
+      }
+      expect(error.kind).toBe(wasmModule.UtreexoErrorKind.UnsupportedVersion);
     });
   });
 
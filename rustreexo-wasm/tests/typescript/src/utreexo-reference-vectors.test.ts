@@ -438,6 +438,41 @@ describe('Utreexo Reference Vector Tests', () => {
     });
   });
 
+  describe('Deterministic Ordering Conformance', () => {
+    it('should describe the ordering rules', () => {
+      const info = wasmModule.ordering_info();
+      expect(info.proof_hash_order).toMatch(/ascending position/);
+      expect(info.stump_roots).toBeDefined();
+      expect(info.pollard_roots).toBeDefined();
+    });
+
+    it('should produce fixed roots for a fixed sequence of operations', () => {
+      const leafHashes = [0, 1, 2, 3, 4, 5, 6, 7].map((preimage) => hashFromU8(preimage));
+      const emptyProof = JSON.stringify({ targets: [], hashes: [] });
+
+      const stump = new wasmModule.WasmStump();
+      const pollard = new wasmModule.WasmPollard();
+      wasmObjects.push(stump, pollard);
+
+      const additions = leafHashes.slice(0, 7).map((hash) => ({ hash, remember: true }));
+      stump.modify(emptyProof, leafHashes.slice(0, 7), []);
+      pollard.modify(emptyProof, JSON.stringify(additions), []);
+      expect(stump.roots()).toEqual(testCasesData.insertion_tests[1]!.expected_roots);
+
+      const dels = [leafHashes[2], leafHashes[5]];
+      const proof = pollard.batch_proof(dels);
+      expect(JSON.parse(proof).targets).toEqual([2, 5]);
+
+      stump.modify(proof, [leafHashes[7]], dels);
+      pollard.modify(proof, JSON.stringify([{ hash: leafHashes[7], remember: true }]), dels);
+
+      const expectedRoots = ['271559b9b573f54289130fe4f88d0aeb7346a90a3adb675f63005f8148b86a6a'];
+      expect(stump.num_leaves()).toBe(8n);
+      expect(stump.roots()).toEqual(expectedRoots);
+      expect(pollard.roots()).toEqual(expectedRoots);
+    });
+  });
+
   describe('Error Handling with Real Data', () => {
     it('should handle invalid hash inputs gracefully', () => {
       expect(() => new wasmModule.Hash('invalid_hash')).toThrow();